@@ -1,6 +1,13 @@
-use cpf::{Framebuffer, FramebufferConfigExt, Painter};
+use cpf::glow::{self, HasContext};
+use cpf::{
+    Bgra8, FrameInfo, Framebuffer, FramebufferBuilder, FramebufferError, PaintOutcome, Painter,
+    PixelFormat,
+};
+use std::time::{Duration, Instant};
+use winit::event::StartCause;
 use winit::event_loop::ActiveEventLoop;
-use winit::event_loop::EventLoop;
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::{Key, NamedKey};
 
 #[derive(Clone, Copy)]
 pub struct Config {
@@ -13,17 +20,16 @@ struct Surface<Format> {
     window: winit::window::Window,
 }
 
-// NOTE: [u8; 4] is the only supported pixel format as of now.
-pub struct App<P: Painter<Pixel = [u8; 4]>> {
+pub struct App<P: Painter<Pixel: PixelFormat>> {
     // application logic
     config: Config,
     painter: P,
 
     // window drawing
-    surface: Option<Surface<[u8; 4]>>,
+    surface: Option<Surface<P::Pixel>>,
 }
 
-impl<P: Painter<Pixel = [u8; 4]>> App<P> {
+impl<P: Painter<Pixel: PixelFormat>> App<P> {
     pub fn new(width: usize, height: usize, painter: P) -> Self {
         Self {
             config: Config { width, height },
@@ -33,19 +39,23 @@ impl<P: Painter<Pixel = [u8; 4]>> App<P> {
     }
 }
 
-impl<P: Painter<Pixel = [u8; 4]>> winit::application::ApplicationHandler for App<P> {
+impl<P: Painter<Pixel: PixelFormat>> winit::application::ApplicationHandler for App<P> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.surface.is_none() {
-            let (window, framebuffer) = {
-                Framebuffer::init_with_ext(
-                    event_loop,
-                    self.config.width,
-                    self.config.height,
-                    Some(FramebufferConfigExt {
-                        clear_color: Some([0.3, 0.4, 0.7, 1.0]),
-                    }),
-                )
+            let (window, framebuffer) = match FramebufferBuilder::new()
+                .size(self.config.width, self.config.height)
+                .title("cpf")
+                .clear_color([0.3, 0.4, 0.7, 1.0])
+                .build(event_loop)
+            {
+                Ok(surface) => surface,
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                    return;
+                }
             };
+            cpf::set_strict_errors(cfg!(debug_assertions));
 
             self.surface = Some(Surface {
                 framebuffer,
@@ -55,9 +65,17 @@ impl<P: Painter<Pixel = [u8; 4]>> winit::application::ApplicationHandler for App
         }
     }
 
+    fn new_events(&mut self, _: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some(Surface { window, .. }) = self.surface.as_ref() {
+                window.request_redraw();
+            }
+        }
+    }
+
     fn window_event(
         &mut self,
-        _: &ActiveEventLoop,
+        event_loop: &ActiveEventLoop,
         _: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
@@ -68,13 +86,125 @@ impl<P: Painter<Pixel = [u8; 4]>> winit::application::ApplicationHandler for App
                     window,
                 }) = self.surface.as_mut()
                 {
-                    framebuffer.draw(&mut self.painter);
-
-                    // TODO: draw ui
-
-                    window.request_redraw();
+                    // redraws requested by the system must always be served, the window contents
+                    // may be lost
+                    let outcome = match framebuffer.draw(&mut self.painter) {
+                        Ok(outcome) => outcome,
+                        // e.g. after a driver reset, start over on a new context
+                        Err(FramebufferError::ContextLost) => {
+                            framebuffer.reinit().unwrap();
+                            window.request_redraw();
+                            return;
+                        }
+                        Err(err) => panic!("{err}"),
+                    };
+
+                    // a UI can be drawn on top with the `ui` feature, see the overlay example
+
+                    match outcome {
+                        // keep redrawing only while the painter has something new to show
+                        PaintOutcome::Continue if self.painter.is_dirty() => {
+                            event_loop.set_control_flow(ControlFlow::Poll);
+                            window.request_redraw();
+                        }
+                        PaintOutcome::Continue => event_loop.set_control_flow(ControlFlow::Wait),
+                        // the redraw is requested once the timer fires, see `new_events`
+                        PaintOutcome::Redraw { when } => {
+                            event_loop.set_control_flow(ControlFlow::WaitUntil(when))
+                        }
+                        PaintOutcome::Stop => event_loop.exit(),
+                        // minimized or occluded, `Resized` and `Occluded` request the next redraw
+                        PaintOutcome::Hidden => event_loop.set_control_flow(ControlFlow::Wait),
+                    }
                 };
             }
+            winit::event::WindowEvent::KeyboardInput { event, .. } => {
+                if let Some(Surface {
+                    framebuffer,
+                    window,
+                }) = self.surface.as_mut()
+                {
+                    if event.state.is_pressed() {
+                        match &event.logical_key {
+                            #[cfg(feature = "png")]
+                            Key::Character(c) if c == "s" => {
+                                match framebuffer.save_png(std::path::Path::new("screenshot.png")) {
+                                    Ok(()) => println!("saved screenshot.png"),
+                                    Err(err) => eprintln!("{err}"),
+                                }
+                            }
+                            Key::Character(c) if c == "m" => {
+                                println!("{:?}", framebuffer.last_metrics())
+                            }
+                            key => pan_zoom(framebuffer, key),
+                        }
+                    }
+                    window.request_redraw();
+                }
+            }
+            winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(Surface {
+                    framebuffer,
+                    window,
+                }) = self.surface.as_mut()
+                {
+                    let lines = match delta {
+                        winit::event::MouseScrollDelta::LineDelta(_, y) => y,
+                        winit::event::MouseScrollDelta::PixelDelta(position) => {
+                            position.y as f32 / 50.0
+                        }
+                    };
+                    let (offset, zoom) = framebuffer.view();
+                    framebuffer.set_view(offset, zoom * ZOOM_STEP.powf(lines));
+                    window.request_redraw();
+                }
+            }
+            winit::event::WindowEvent::MouseInput { .. }
+            | winit::event::WindowEvent::CursorMoved { .. } => {
+                // wake up on input, the painter may have changed in response
+                if let Some(Surface { window, .. }) = self.surface.as_ref() {
+                    window.request_redraw();
+                }
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                if let Some(Surface {
+                    framebuffer,
+                    window,
+                }) = self.surface.as_mut()
+                {
+                    // minimized windows report a zero size, the framebuffer keeps its old size
+                    // and skips drawing until restored
+                    framebuffer
+                        .resize(size.width as usize, size.height as usize)
+                        .unwrap();
+                    let (width, height) = framebuffer.dimensions();
+                    self.config = Config { width, height };
+                    if framebuffer.is_visible() {
+                        window.request_redraw();
+                    }
+                }
+            }
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                if let Some(Surface {
+                    framebuffer,
+                    window,
+                }) = self.surface.as_mut()
+                {
+                    framebuffer.handle_scale_change(scale_factor, window.inner_size());
+                }
+            }
+            winit::event::WindowEvent::Occluded(occluded) => {
+                if let Some(Surface {
+                    framebuffer,
+                    window,
+                }) = self.surface.as_mut()
+                {
+                    framebuffer.set_occluded(occluded);
+                    if framebuffer.is_visible() {
+                        window.request_redraw();
+                    }
+                }
+            }
             winit::event::WindowEvent::CloseRequested => {
                 std::process::exit(0);
             }
@@ -83,6 +213,27 @@ impl<P: Painter<Pixel = [u8; 4]>> winit::application::ApplicationHandler for App
     }
 }
 
+const ZOOM_STEP: f32 = 1.25;
+
+/// Pan with the arrow keys, zoom with `+` / `-` and reset the view with `0`.
+fn pan_zoom<Format: PixelFormat>(framebuffer: &mut Framebuffer<Format>, key: &Key) {
+    let (mut offset, mut zoom) = framebuffer.view();
+    let step = 0.05 / zoom;
+
+    match key {
+        Key::Named(NamedKey::ArrowLeft) => offset[0] -= step,
+        Key::Named(NamedKey::ArrowRight) => offset[0] += step,
+        Key::Named(NamedKey::ArrowUp) => offset[1] -= step,
+        Key::Named(NamedKey::ArrowDown) => offset[1] += step,
+        Key::Character(c) if c == "+" || c == "=" => zoom *= ZOOM_STEP,
+        Key::Character(c) if c == "-" => zoom /= ZOOM_STEP,
+        Key::Character(c) if c == "0" => (offset, zoom) = ([0.0, 0.0], 1.0),
+        _ => return,
+    }
+    framebuffer.set_view(offset, zoom);
+}
+
+#[derive(Default)]
 pub struct BasicPainter;
 
 impl Painter for BasicPainter {
@@ -97,121 +248,281 @@ impl Painter for BasicPainter {
     }
 }
 
-pub struct RGBPainter {
-    width: usize,
-    height: usize,
-}
-
-impl From<Config> for RGBPainter {
-    fn from(Config { width, height }: Config) -> Self {
-        Self { width, height }
-    }
-}
+#[derive(Default)]
+pub struct RGBPainter;
 
 impl Painter for RGBPainter {
     type Pixel = [u8; 4];
 
-    fn paint(&mut self, pixels: &mut [Self::Pixel]) {
-        const RED: [u8; 4] = [255, 0, 0, 0];
-        const GREEN: [u8; 4] = [0, 255, 0, 0];
-        const BLUE: [u8; 4] = [0, 0, 255, 0];
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
 
         for (index, pixel) in pixels.iter_mut().enumerate() {
-            let color = match (index / (self.width / 4 * self.height)) % 4 {
+            let color = match (index / (frame.width / 4 * frame.height)) % 4 {
                 0 => RED,
                 1 => GREEN,
                 2 => BLUE,
-                3 => [0, 0, 0, 0],
+                3 => [0, 0, 0, 255],
                 _ => unreachable!(),
             };
             *pixel = color;
         }
+        PaintOutcome::Continue
     }
 }
 
-pub struct CheckAlignment {
-    width: usize,
-    height: usize,
+/// Marks each corner with an 8x8 block, so a misaligned or cropped frame is easy to spot:
+/// red top-left, yellow top-right, green bottom-left and cyan bottom-right, for the default
+/// top-left [`cpf::Origin`].
+#[derive(Default)]
+pub struct CheckAlignment;
+
+impl CheckAlignment {
+    const SIZE: usize = 8;
 }
 
-impl From<Config> for CheckAlignment {
-    fn from(Config { width, height }: Config) -> Self {
-        Self { width, height }
+impl Painter for CheckAlignment {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let mut view = frame.view(pixels);
+        view.fill([0, 0, 0, 255]);
+        let (width, height) = (view.width(), view.height());
+        let size = Self::SIZE.min(width).min(height);
+        let (right, bottom) = (width - size, height - size);
+        view.fill_rect(0, 0, size, size, [255, 0, 0, 255]);
+        view.fill_rect(right, 0, size, size, [255, 255, 0, 255]);
+        view.fill_rect(0, bottom, size, size, [0, 255, 0, 255]);
+        view.fill_rect(right, bottom, size, size, [0, 255, 255, 255]);
+        PaintOutcome::Continue
     }
 }
 
-impl Painter for CheckAlignment {
+#[derive(Default)]
+pub struct LinePainter;
+
+impl Painter for LinePainter {
     type Pixel = [u8; 4];
 
-    fn paint(&mut self, pixels: &mut [Self::Pixel]) {
-        for (index, pixel) in pixels.iter_mut().enumerate() {
-            if index < 8 {
-                *pixel = [255, 0, 0, 0]
-            }
-            if self.width - 8 < index && index < self.width {
-                *pixel = [255, 255, 0, 0]
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let mut view = frame.view(pixels);
+        view.fill([0, 0, 0, 255]);
+        // white diagonal (y = x), 8 pixels thick on either side
+        for y in 0..view.height() {
+            for x in y.saturating_sub(8)..=y + 8 {
+                if let Some(pixel) = view.get_mut(x, y) {
+                    *pixel = [255, 255, 255, 255];
+                }
             }
+        }
+        PaintOutcome::Continue
+    }
+}
 
-            if self.width * (self.height - 1) < index && index < self.width * (self.height - 1) + 8
-            {
-                *pixel = [0, 255, 0, 0]
-            }
+/// Outlines the outermost pixels - a missing edge means the quad doesn't cover the window.
+#[derive(Default)]
+pub struct BorderPainter;
 
-            if self.width * self.height - 8 < index && index < self.width * self.height {
-                *pixel = [0, 255, 255, 0]
-            }
+impl Painter for BorderPainter {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let mut view = frame.view(pixels);
+        let (width, height) = (view.width(), view.height());
+        view.fill([255, 255, 255, 255]);
+        // windows can be resized down to a single pixel, which leaves no inside
+        if width > 2 && height > 2 {
+            view.fill_rect(1, 1, width - 2, height - 2, [0, 0, 0, 255]);
         }
+        PaintOutcome::Continue
     }
 }
 
-pub struct LinePainter {
-    width: usize,
-    height: usize,
+/// Diagonal gradient in a 3 byte per pixel format.
+///
+/// Run it at an odd width to verify rows which are not 4 byte aligned don't get sheared.
+#[derive(Default)]
+pub struct RGBGradient;
+
+impl Painter for RGBGradient {
+    type Pixel = [u8; 3];
+
+    fn paint_frame(
+        &mut self,
+        pixels: &mut [Self::Pixel],
+        &FrameInfo { width, height, .. }: &FrameInfo,
+    ) -> PaintOutcome {
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = (index % width, index / width);
+            *pixel = [
+                (x * 255 / width) as u8,
+                (y * 255 / height) as u8,
+                ((x + y) * 255 / (width + height)) as u8,
+            ];
+        }
+        PaintOutcome::Continue
+    }
+}
+
+/// Concentric rings of a scalar field, shown in grayscale.
+#[derive(Default)]
+pub struct GrayscaleField;
+
+impl Painter for GrayscaleField {
+    type Pixel = u8;
+
+    fn paint_frame(
+        &mut self,
+        pixels: &mut [Self::Pixel],
+        &FrameInfo { width, height, .. }: &FrameInfo,
+    ) -> PaintOutcome {
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let x = (index % width) as f32 - cx;
+            let y = (index / width) as f32 - cy;
+            let value = ((x * x + y * y).sqrt() / 16.0).sin();
+            *pixel = ((value + 1.0) * 127.5) as u8;
+        }
+        PaintOutcome::Continue
+    }
 }
 
-impl From<Config> for LinePainter {
-    fn from(Config { width, height }: Config) -> Self {
-        Self { width, height }
+/// Fills the window with pure blue written in BGRA order - any other color means swapped channels.
+#[derive(Default)]
+pub struct BgraBlue;
+
+impl Painter for BgraBlue {
+    type Pixel = Bgra8;
+
+    fn paint(&mut self, pixels: &mut [Self::Pixel]) {
+        pixels.fill(Bgra8([255, 0, 0, 255]));
     }
 }
 
-impl Painter for LinePainter {
+/// Paint [`BgraBlue`] into a headless framebuffer and check the texture holds pure blue.
+///
+/// The texture is read back in RGBA order, so a swapped channel order in the upload shows up
+/// as red instead of round-tripping unnoticed.
+fn check_bgra() -> anyhow::Result<()> {
+    let (width, height) = (64, 64);
+    let mut framebuffer = Framebuffer::<Bgra8>::init_headless(width, height)?;
+    framebuffer.update(&mut BgraBlue)?;
+
+    let mut pixels = vec![[0u8; 4]; width * height];
+    let gl = framebuffer.gl();
+    unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(framebuffer.texture()));
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.get_tex_image(
+            glow::TEXTURE_2D,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(pixels.as_flattened_mut()),
+        );
+        gl.bind_texture(glow::TEXTURE_2D, None);
+    }
+    anyhow::ensure!(
+        pixels.iter().all(|&pixel| pixel == [0, 0, 255, 255]),
+        "expected pure blue, found {:?}",
+        pixels[0]
+    );
+    println!("bgra: texture is pure blue");
+    Ok(())
+}
+
+/// Scrolling color bands animated by the frame clock.
+#[derive(Default)]
+pub struct Plasma;
+
+impl Painter for Plasma {
     type Pixel = [u8; 4];
 
-    fn paint(&mut self, pixels: &mut [Self::Pixel]) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                // Calculate the distance from the main diagonal (y = x)
-                let dist = (y as isize - x as isize).abs();
-
-                // If the pixel is within the 8-pixel thickness of the diagonal, draw it
-                if dist <= 8 {
-                    // Access the pixel at (x, y)
-                    let pixel = &mut pixels[y * self.width + x];
-
-                    pixel[0] = 255; // Red
-                    pixel[1] = 255; // Green
-                    pixel[2] = 255; // Blue
-                    pixel[3] = 255; // Alpha (fully opaque)
-                }
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let t = frame.elapsed.as_secs_f32();
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let value = ((x as f32 / 32.0 + t).sin() + (y as f32 / 24.0 - t).cos()) * 63.0;
+                let value = (value + 128.0) as u8;
+                *pixel = [value, 255 - value, 128, 255];
             }
         }
+        // roughly 30 frames per second is plenty for this, sleep in between
+        PaintOutcome::Redraw {
+            when: Instant::now() + Duration::from_millis(33),
+        }
     }
 }
 
-pub fn run<P: Painter<Pixel = [u8; 4]> + From<Config>>(
+/// Square sliding across a static background, uploading only the pixels it touched.
+#[derive(Default)]
+pub struct SlidingSquare {
+    x: usize,
+    size: Option<(usize, usize)>,
+    dirty_rect: Option<[usize; 4]>,
+}
+
+impl SlidingSquare {
+    const SIZE: usize = 32;
+}
+
+impl Painter for SlidingSquare {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let mut view = frame.view(pixels);
+        let size = Self::SIZE.min(view.width()).min(view.height());
+        let y = (view.height() - size) / 2;
+        let last = self.x;
+        self.x = (last + 1) % (view.width() - size + 1);
+
+        let resized =
+            self.size.replace((frame.width, frame.height)) != Some((frame.width, frame.height));
+        if resized || self.x < last {
+            // the texture starts out undefined and wrapping around moves the square too far
+            view.fill([0, 0, 0, 255]);
+            self.dirty_rect = None;
+        } else {
+            // erase the column left behind
+            view.fill_rect(last, y, 1, size, [0, 0, 0, 255]);
+            self.dirty_rect = Some([last, y, size + 1, size]);
+        }
+        view.fill_rect(self.x, y, size, size, [255, 128, 0, 255]);
+        PaintOutcome::Continue
+    }
+
+    fn dirty_rect(&self) -> Option<[usize; 4]> {
+        self.dirty_rect
+    }
+}
+
+pub fn run<P: Painter<Pixel: PixelFormat> + Default>(
     width: usize,
     height: usize,
 ) -> anyhow::Result<()> {
-    let config = Config { width, height };
-    let mut app = App::new(width, height, P::from(config));
+    let mut app = App::new(width, height, P::default());
 
     let event_loop = EventLoop::new()?;
-    event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+    event_loop.set_control_flow(ControlFlow::Poll);
 
     Ok(event_loop.run_app(&mut app)?)
 }
 
 pub fn main() -> anyhow::Result<()> {
-    run::<LinePainter>(640, 640)
+    // pick the painter with the first argument, e.g. `cargo run --example basic -- rgb`
+    match std::env::args().nth(1).as_deref() {
+        Some("basic") => run::<BasicPainter>(640, 640),
+        Some("stripes") => run::<RGBPainter>(640, 640),
+        Some("alignment") => run::<CheckAlignment>(640, 640),
+        Some("border") => run::<BorderPainter>(640, 640),
+        Some("rgb") => run::<RGBGradient>(639, 480),
+        Some("gray") => run::<GrayscaleField>(640, 640),
+        Some("bgra") => run::<BgraBlue>(640, 640),
+        Some("bgra-check") => check_bgra(),
+        Some("plasma") => run::<Plasma>(640, 640),
+        Some("square") => run::<SlidingSquare>(640, 640),
+        _ => run::<LinePainter>(640, 640),
+    }
 }
@@ -0,0 +1,31 @@
+//! Draws the built-in test pattern in every pixel format without a window and checks that
+//! reading it back returns the same pixels.
+use cpf::{Bgra8, Framebuffer, PixelFormat, TestPattern};
+
+fn check<F: PixelFormat + PartialEq + std::fmt::Debug>(name: &str) -> anyhow::Result<()> {
+    let (width, height) = (200, 120);
+    let mut framebuffer = Framebuffer::<F>::init_headless(width, height)?;
+    let mut pattern = TestPattern::new(16).without_label();
+    framebuffer.update(&mut pattern)?;
+
+    let pixels = framebuffer.read_pixels()?;
+    for (index, &pixel) in pixels.iter().enumerate() {
+        let expected = pattern.pixel(index % width, index / width, width, height);
+        anyhow::ensure!(
+            pixel == expected,
+            "{name}: pixel {index} read back as {pixel:?} instead of {expected:?}"
+        );
+    }
+    println!("{name}: ok");
+    Ok(())
+}
+
+pub fn main() -> anyhow::Result<()> {
+    check::<[u8; 4]>("rgba8")?;
+    check::<[u8; 3]>("rgb8")?;
+    check::<Bgra8>("bgra8")?;
+    check::<u8>("r8")?;
+    check::<[u16; 4]>("rgba16")?;
+    check::<[f32; 4]>("rgba32f")?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Press `f` to toggle borderless fullscreen. The framebuffer is reallocated for every new
+//! window size, so the grid lines stay one pixel wide instead of being stretched.
+use cpf::{FrameInfo, Framebuffer, FramebufferBuilder, PaintOutcome, Painter};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::Key;
+use winit::window::Fullscreen;
+
+/// One pixel wide lines every 32 pixels, with a red border around the whole frame.
+struct Grid;
+
+impl Painter for Grid {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if x == 0 || y == 0 || x == frame.width - 1 || y == frame.height - 1 {
+                    [255, 0, 0, 255]
+                } else if x % 32 == 0 || y % 32 == 0 {
+                    [200, 200, 200, 255]
+                } else {
+                    [20, 20, 40, 255]
+                };
+            }
+        }
+        PaintOutcome::Continue
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+struct App {
+    surface: Option<(winit::window::Window, Framebuffer<[u8; 4]>)>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match FramebufferBuilder::new()
+                .size(640, 480)
+                .title("cpf fullscreen")
+                .build(event_loop)
+            {
+                Ok(surface) => self.surface = Some(surface),
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((window, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                framebuffer.draw(&mut Grid).unwrap();
+            }
+            winit::event::WindowEvent::KeyboardInput { event, .. } if event.state.is_pressed() => {
+                if matches!(&event.logical_key, Key::Character(c) if c == "f") {
+                    let fullscreen = match window.fullscreen() {
+                        Some(_) => None,
+                        None => Some(Fullscreen::Borderless(None)),
+                    };
+                    framebuffer.set_fullscreen(window, fullscreen);
+                    window.request_redraw();
+                }
+            }
+            // entering and leaving fullscreen arrive here as well
+            winit::event::WindowEvent::Resized(size) => {
+                framebuffer
+                    .resize(size.width as usize, size.height as usize)
+                    .unwrap();
+                window.request_redraw();
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut App::default())?)
+}
@@ -0,0 +1,106 @@
+//! Paints linear HDR values above 1.0, tone maps them for display and reads the frame back to
+//! confirm they survive the upload.
+use cpf::glow::HasContext;
+use cpf::{FrameInfo, Framebuffer, FramebufferBuilder, PaintOutcome, Painter, ReadbackToken};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 480;
+
+/// Reinhard tone mapping, so values above 1.0 stay distinguishable on screen.
+const TONE_MAPPING: &str = r#"
+#version 330 core
+out vec4 FragColor;
+in vec2 TexCoord;
+uniform sampler2D texture1;
+uniform float exposure;
+void main()
+{
+    vec3 color = texture(texture1, TexCoord).rgb * exposure;
+    FragColor = vec4(color / (color + 1.0), 1.0);
+}
+"#;
+
+/// Horizontal ramp from 0.0 to 4.0 in every channel.
+struct HdrRamp;
+
+impl Painter for HdrRamp {
+    type Pixel = [f32; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            let value = (index % frame.width) as f32 / frame.width as f32 * 4.0;
+            *pixel = [value, value, value, 1.0];
+        }
+        PaintOutcome::Continue
+    }
+}
+
+#[derive(Default)]
+struct App {
+    surface: Option<(winit::window::Window, Framebuffer<[f32; 4]>)>,
+    readback: Option<ReadbackToken>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match FramebufferBuilder::new()
+                .size(WIDTH, HEIGHT)
+                .fragment_shader(TONE_MAPPING)
+                .build(event_loop)
+            {
+                Ok((window, framebuffer)) => {
+                    let gl = framebuffer.gl();
+                    unsafe {
+                        gl.use_program(Some(framebuffer.program()));
+                        let exposure = gl.get_uniform_location(framebuffer.program(), "exposure");
+                        gl.uniform_1_f32(exposure.as_ref(), 1.5);
+                    }
+                    self.surface = Some((window, framebuffer));
+                }
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                if let Some((window, framebuffer)) = self.surface.as_mut() {
+                    framebuffer.draw(&mut HdrRamp).unwrap();
+
+                    match self.readback.as_ref() {
+                        None => self.readback = Some(framebuffer.begin_readback().unwrap()),
+                        Some(token) => {
+                            if let Some(pixels) = framebuffer.try_finish_readback(token).unwrap() {
+                                let max =
+                                    pixels.iter().map(|pixel| pixel[0]).fold(f32::MIN, f32::max);
+                                println!("brightest value read back: {max}");
+                                assert!(max > 1.0, "HDR values were clamped during upload");
+                                event_loop.exit();
+                            }
+                        }
+                    }
+
+                    window.request_redraw();
+                }
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut App::default())?)
+}
@@ -0,0 +1,38 @@
+//! Renders a frame without a window and saves it to `headless.png`, no display needed.
+use cpf::{FrameInfo, Framebuffer, PaintOutcome, Painter};
+use std::path::Path;
+
+/// Red and green ramps along the axes.
+struct Gradient;
+
+impl Painter for Gradient {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = [
+                    (x * 255 / frame.width) as u8,
+                    (y * 255 / frame.height) as u8,
+                    128,
+                    255,
+                ];
+            }
+        }
+        PaintOutcome::Stop
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut framebuffer = Framebuffer::init_headless(256, 256)?;
+    let capabilities = framebuffer.capabilities();
+    let (major, minor) = capabilities.version;
+    println!(
+        "rendering with {} (OpenGL {major}.{minor})",
+        capabilities.renderer
+    );
+    framebuffer.update(&mut Gradient)?;
+    framebuffer.save_png(Path::new("headless.png"))?;
+    println!("saved headless.png");
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! The shortest program showing something, with the event loop handled by `run_painter`.
+use cpf::{FrameInfo, PaintOutcome, Painter};
+
+/// Diagonal color bands drifting across the window.
+struct Bands;
+
+impl Painter for Bands {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let shift = (frame.elapsed.as_secs_f32() * 60.0) as usize;
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let band = ((x + y + shift) / 16 % 3) as u8;
+                *pixel = [band * 100, 255 - band * 100, 128, 255];
+            }
+        }
+        PaintOutcome::Continue
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Ok(cpf::run_painter(640, 480, Bands)?)
+}
@@ -0,0 +1,79 @@
+//! Two windows drawn from one event loop, the second sharing the display of the first.
+use cpf::{fn_painter, Framebuffer};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+struct View {
+    window: Window,
+    framebuffer: Framebuffer<[u8; 4]>,
+    color: [u8; 4],
+}
+
+#[derive(Default)]
+struct App {
+    views: Vec<View>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.views.is_empty() {
+            return;
+        }
+        let surfaces = Framebuffer::init(event_loop, 320, 240).and_then(|(window, framebuffer)| {
+            let second = Framebuffer::init_shared(&framebuffer, event_loop, 320, 240)?;
+            Ok([(window, framebuffer), second])
+        });
+        match surfaces {
+            Ok(surfaces) => {
+                let colors = [[200, 60, 60, 255], [60, 60, 200, 255]];
+                for ((window, framebuffer), color) in surfaces.into_iter().zip(colors) {
+                    self.views.push(View {
+                        window,
+                        framebuffer,
+                        color,
+                    });
+                }
+            }
+            Err(err) => {
+                eprintln!("failed to initialize the framebuffers: {err}");
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some(view) = self
+            .views
+            .iter_mut()
+            .find(|view| view.window.id() == window_id)
+        else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                // every window has its own context, `draw` switches to it
+                let color = view.color;
+                view.framebuffer
+                    .draw(&mut fn_painter(|pixels| pixels.fill(color)))
+                    .unwrap();
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                view.framebuffer
+                    .resize(size.width as usize, size.height as usize)
+                    .unwrap();
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut App::default())?)
+}
@@ -0,0 +1,109 @@
+//! Draws an egui window over the framebuffer, run with `--features ui`.
+use cpf::ui::{egui, EguiOverlay};
+use cpf::{FrameInfo, Framebuffer, FramebufferBuilder, PaintOutcome, Painter};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+/// Solid color picked in the UI.
+struct Fill {
+    color: [u8; 4],
+}
+
+impl Painter for Fill {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], _: &FrameInfo) -> PaintOutcome {
+        pixels.fill(self.color);
+        PaintOutcome::Continue
+    }
+}
+
+struct Surface {
+    // declared first, the overlay has to go before the framebuffer's context
+    overlay: EguiOverlay,
+    framebuffer: Framebuffer<[u8; 4]>,
+    window: winit::window::Window,
+}
+
+struct App {
+    painter: Fill,
+    surface: Option<Surface>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match FramebufferBuilder::new()
+                .size(640, 480)
+                .title("cpf overlay")
+                .build(event_loop)
+            {
+                Ok((window, framebuffer)) => {
+                    let overlay = EguiOverlay::new(event_loop, &framebuffer);
+                    self.surface = Some(Surface {
+                        overlay,
+                        framebuffer,
+                        window,
+                    });
+                }
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some(Surface {
+            overlay,
+            framebuffer,
+            window,
+        }) = self.surface.as_mut()
+        else {
+            return;
+        };
+        if overlay.on_window_event(window, &event).repaint {
+            window.request_redraw();
+        }
+
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                framebuffer.update(&mut self.painter).unwrap();
+                framebuffer.blit().unwrap();
+
+                let color = &mut self.painter.color;
+                overlay.run(window, framebuffer, |ctx| {
+                    egui::Window::new("cpf").show(ctx, |ui| {
+                        ui.label("fill color");
+                        ui.color_edit_button_srgba_unmultiplied(color);
+                    });
+                });
+
+                framebuffer.swap_buffers().unwrap();
+            }
+            winit::event::WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                framebuffer
+                    .resize(size.width as usize, size.height as usize)
+                    .unwrap();
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut app = App {
+        painter: Fill {
+            color: [40, 60, 120, 255],
+        },
+        surface: None,
+    };
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut app)?)
+}
@@ -0,0 +1,42 @@
+//! Uploads a 16 bit per channel gradient without a window and checks that reading it back
+//! returns every bit, also those below the 8 bits a display shows.
+use cpf::{FrameInfo, Framebuffer, PaintOutcome, Painter};
+
+/// Red ramp across the image in steps of one, green ramp down with the low byte set.
+struct Gradient;
+
+impl Gradient {
+    fn pixel(x: usize, y: usize) -> [u16; 4] {
+        [x as u16, (y as u16) << 8 | 0xff, 0x1234, u16::MAX]
+    }
+}
+
+impl Painter for Gradient {
+    type Pixel = [u16; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = Self::pixel(x, y);
+            }
+        }
+        PaintOutcome::Stop
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let (width, height) = (1024, 256);
+    let mut framebuffer = Framebuffer::<[u16; 4]>::init_headless(width, height)?;
+    framebuffer.update(&mut Gradient)?;
+
+    let pixels = framebuffer.read_pixels()?;
+    for (index, &pixel) in pixels.iter().enumerate() {
+        let expected = Gradient::pixel(index % width, index / width);
+        anyhow::ensure!(
+            pixel == expected,
+            "pixel {index} read back as {pixel:04x?} instead of {expected:04x?}"
+        );
+    }
+    println!("all {} pixels survived with 16 bits", pixels.len());
+    Ok(())
+}
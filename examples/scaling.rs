@@ -0,0 +1,144 @@
+//! Shows a 64x64 framebuffer in a larger window, press `1`, `2` or `3` to switch the scaling mode.
+//!
+//! `cargo run --example scaling -- letterbox` instead fits a 16:9 framebuffer into a square
+//! window, which should leave bars of the clear color above and below.
+//!
+//! `cargo run --example scaling -- noclear` fits the framebuffer into a wider window without
+//! clearing it every frame: the window is only cleared while the back buffer's contents are
+//! undefined, see [`Framebuffer::buffer_age`]. As drawing is confined to the content region,
+//! the bars left and right should keep the clear color rather than show stale pixels.
+use cpf::glow::HasContext;
+use cpf::{Filter, FrameInfo, Framebuffer, FramebufferBuilder, PaintOutcome, Painter, ScalingMode};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::Key;
+
+/// 8x8 checkerboard, when scaled by a fraction its squares visibly differ in size.
+struct Checkerboard;
+
+impl Painter for Checkerboard {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if (x / 8 + y / 8) % 2 == 0 {
+                    [255, 255, 255, 255]
+                } else {
+                    [200, 40, 40, 255]
+                };
+            }
+        }
+        PaintOutcome::Continue
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+/// Which demo to show, picked by the first argument.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Demo {
+    Modes,
+    Letterbox,
+    NoClear,
+}
+
+struct App {
+    demo: Demo,
+    surface: Option<(winit::window::Window, Framebuffer<[u8; 4]>)>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            let builder = match self.demo {
+                Demo::Modes => FramebufferBuilder::new()
+                    .size(64, 64)
+                    .window_size(720, 540)
+                    .scaling(ScalingMode::IntegerCenter),
+                Demo::Letterbox => FramebufferBuilder::new()
+                    .size(160, 90)
+                    .window_size(540, 540)
+                    .scaling(ScalingMode::FitContain),
+                Demo::NoClear => FramebufferBuilder::new()
+                    .size(64, 64)
+                    .window_size(720, 360)
+                    .scaling(ScalingMode::FitContain),
+            };
+            match builder
+                .title("cpf scaling")
+                .clear_color([0.1, 0.1, 0.1, 1.0])
+                .mag_filter(Filter::Nearest)
+                .build(event_loop)
+            {
+                Ok(surface) => self.surface = Some(surface),
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((window, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested if self.demo == Demo::NoClear => {
+                if framebuffer.buffer_age().is_none() {
+                    // nothing to keep, start the back buffer over with the clear color
+                    unsafe { framebuffer.gl().clear(cpf::glow::COLOR_BUFFER_BIT) };
+                }
+                framebuffer.update(&mut Checkerboard).unwrap();
+                // unlike `draw`, this leaves the window outside of the content region as it is
+                let size = framebuffer.surface_size();
+                framebuffer
+                    .blit_into([0, 0, size.width as i32, size.height as i32])
+                    .unwrap();
+                framebuffer.swap_buffers().unwrap();
+            }
+            winit::event::WindowEvent::RedrawRequested => {
+                framebuffer.draw(&mut Checkerboard).unwrap();
+            }
+            winit::event::WindowEvent::KeyboardInput { event, .. } if event.state.is_pressed() => {
+                let scaling = match &event.logical_key {
+                    Key::Character(c) if c == "1" => ScalingMode::Stretch,
+                    Key::Character(c) if c == "2" => ScalingMode::IntegerCenter,
+                    Key::Character(c) if c == "3" => ScalingMode::FitContain,
+                    _ => return,
+                };
+                println!("{scaling:?}");
+                framebuffer.set_scaling(scaling);
+                window.request_redraw();
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                // keep the pixels, only the surface they are scaled to changes
+                framebuffer.resize_surface(size.width as usize, size.height as usize);
+                window.request_redraw();
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let demo = match std::env::args().nth(1).as_deref() {
+        Some("letterbox") => Demo::Letterbox,
+        Some("noclear") => Demo::NoClear,
+        _ => Demo::Modes,
+    };
+    let mut app = App {
+        demo,
+        surface: None,
+    };
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut app)?)
+}
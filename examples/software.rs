@@ -0,0 +1,93 @@
+//! Runs a painter on OpenGL if possible and in software otherwise, run with
+//! `--features software`. Pass `software` as the first argument to skip OpenGL.
+use cpf::soft::{AutoFramebuffer, SoftwareFramebuffer};
+use cpf::{FrameInfo, PaintOutcome, Painter};
+use std::sync::Arc;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+/// Diagonal stripes scrolling to the right.
+struct Stripes;
+
+impl Painter for Stripes {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let shift = frame.frame as usize;
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if ((x + y).wrapping_sub(shift) / 16).is_multiple_of(2) {
+                    [230, 180, 40, 255]
+                } else {
+                    [30, 30, 30, 255]
+                };
+            }
+        }
+        PaintOutcome::Continue
+    }
+}
+
+struct App {
+    force_software: bool,
+    surface: Option<(Arc<winit::window::Window>, AutoFramebuffer)>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            let surface = if self.force_software {
+                SoftwareFramebuffer::init(event_loop, 640, 480)
+                    .map(|(window, framebuffer)| (window, AutoFramebuffer::Software(framebuffer)))
+            } else {
+                AutoFramebuffer::init(event_loop, 640, 480)
+            };
+            match surface {
+                Ok((window, framebuffer)) => {
+                    let backend = if framebuffer.is_software() {
+                        "software"
+                    } else {
+                        "OpenGL"
+                    };
+                    window.set_title(&format!("cpf ({backend})"));
+                    self.surface = Some((window, framebuffer));
+                }
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((window, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                framebuffer.draw(&mut Stripes).unwrap();
+                window.request_redraw();
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                framebuffer
+                    .resize(size.width as usize, size.height as usize)
+                    .unwrap();
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let mut app = App {
+        force_software: std::env::args().nth(1).as_deref() == Some("software"),
+        surface: None,
+    };
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut app)?)
+}
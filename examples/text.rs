@@ -0,0 +1,51 @@
+//! Frame counter and frame rate, capped at 30 frames per second, drawn over an animation with
+//! the built-in bitmap font.
+use cpf::text::{draw_text, text_size, GLYPH_SIZE};
+use cpf::{FrameInfo, PaintOutcome, Painter};
+
+#[derive(Default)]
+struct Stats {
+    // smoothed, the rate of a single frame jumps around too much to read
+    fps: f32,
+}
+
+impl Painter for Stats {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let t = frame.elapsed.as_secs_f32();
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let wave = (x as f32 / 40.0 + t).sin() * (y as f32 / 30.0 - t).cos() * 0.5 + 0.5;
+                *pixel = [(wave * 80.0) as u8, (wave * 160.0) as u8, 120, 255];
+            }
+        }
+
+        if !frame.dt.is_zero() {
+            self.fps += (1.0 / frame.dt.as_secs_f32() - self.fps) * 0.05;
+        }
+        let text = format!("frame {}\nfps {:.0}", frame.frame, self.fps);
+        let (width, height) = text_size(&text);
+        let padding = GLYPH_SIZE / 2;
+        frame.view(pixels).fill_rect(
+            0,
+            0,
+            (width + 2 * padding).min(frame.width),
+            (height + 2 * padding).min(frame.height),
+            [0, 0, 0, 255],
+        );
+        draw_text(
+            pixels,
+            frame.width,
+            &text,
+            padding,
+            padding,
+            [255, 255, 0, 255],
+        );
+        PaintOutcome::Continue
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Ok(cpf::run_painter_limited(640, 480, 30.0, Stats::default())?)
+}
@@ -0,0 +1,74 @@
+//! The test pattern shown four times, once per quadrant of the window, each zoomed further in.
+use cpf::glow::HasContext;
+use cpf::{Framebuffer, FramebufferBuilder, FramebufferError, ScalingMode, TestPattern};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+#[derive(Default)]
+struct App {
+    surface: Option<(winit::window::Window, Framebuffer<[u8; 4]>)>,
+}
+
+/// Clear the window once and draw a differently zoomed view into each quadrant.
+fn draw_tiles(framebuffer: &mut Framebuffer<[u8; 4]>) -> Result<(), FramebufferError> {
+    framebuffer.make_current()?;
+    unsafe { framebuffer.gl().clear(cpf::glow::COLOR_BUFFER_BIT) };
+    let size = framebuffer.surface_size();
+    let (width, height) = (size.width as i32 / 2, size.height as i32 / 2);
+    let quadrants = [[0, height], [width, height], [0, 0], [width, 0]];
+    for (zoom, [x, y]) in [1.0, 2.0, 4.0, 8.0].into_iter().zip(quadrants) {
+        framebuffer.set_view([0.0, 0.0], zoom);
+        framebuffer.blit_into([x, y, width, height])?;
+    }
+    framebuffer.swap_buffers()
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match FramebufferBuilder::new()
+                .size(256, 192)
+                .window_size(800, 600)
+                .title("cpf tiles")
+                .scaling(ScalingMode::FitContain)
+                .clear_color([0.1, 0.1, 0.1, 1.0])
+                .build(event_loop)
+            {
+                Ok((window, mut framebuffer)) => {
+                    // the pattern doesn't change, it is uploaded once and drawn for every redraw
+                    framebuffer
+                        .update(&mut TestPattern::default())
+                        .expect("failed to upload the test pattern");
+                    self.surface = Some((window, framebuffer));
+                }
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((_, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => draw_tiles(framebuffer).unwrap(),
+            winit::event::WindowEvent::Resized(size) => {
+                framebuffer.resize_surface(size.width as usize, size.height as usize);
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut App::default())?)
+}
@@ -0,0 +1,76 @@
+//! A soft-edged disc floating over the desktop in a transparent, undecorated window.
+use cpf::{FrameInfo, Framebuffer, FramebufferBuilder, PaintOutcome, Painter};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+/// Orange disc whose alpha fades out towards the edge, fully transparent around it.
+struct Disc;
+
+impl Painter for Disc {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let radius = frame.width.min(frame.height) as f32 / 2.0;
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let dx = x as f32 + 0.5 - frame.width as f32 / 2.0;
+                let dy = y as f32 + 0.5 - frame.height as f32 / 2.0;
+                let alpha = (1.0 - (dx * dx + dy * dy).sqrt() / radius).clamp(0.0, 1.0);
+                *pixel = [255, 140, 0, (alpha.sqrt() * 255.0) as u8];
+            }
+        }
+        PaintOutcome::Continue
+    }
+}
+
+#[derive(Default)]
+struct App {
+    surface: Option<(winit::window::Window, Framebuffer<[u8; 4]>)>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match FramebufferBuilder::new()
+                .size(320, 320)
+                .title("cpf transparent")
+                .decorations(false)
+                .transparent(true)
+                .build(event_loop)
+            {
+                Ok(surface) => self.surface = Some(surface),
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((_, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                framebuffer.draw(&mut Disc).unwrap();
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                framebuffer.resize_surface(size.width as usize, size.height as usize);
+            }
+            // without decorations there is no close button
+            winit::event::WindowEvent::KeyboardInput { .. }
+            | winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut App::default())?)
+}
@@ -0,0 +1,70 @@
+//! Measures how fast 1080p frames reach the texture for different pixel buffer ring sizes,
+//! with and without orphaning the buffers.
+//!
+//! Runs headless, so it needs no display. Each configuration paints and uploads a warm-up
+//! followed by `FRAMES` timed frames, and reads the last one back so the GPU has finished
+//! every upload before the clock stops.
+use cpf::{FrameInfo, FramebufferBuilder, PaintOutcome, Painter};
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 1920;
+const HEIGHT: usize = 1080;
+const WARM_UP: usize = 10;
+const FRAMES: usize = 200;
+
+/// A bar moving across an otherwise static frame, cheap enough for the upload to dominate.
+struct MovingBar;
+
+impl Painter for MovingBar {
+    type Pixel = [u8; 4];
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let bar = frame.frame as usize * 8 % frame.width;
+        for row in frame.view(pixels).rows_mut() {
+            row.fill([32, 32, 32, 255]);
+            row[bar..(bar + 8).min(frame.width)].fill([255, 255, 255, 255]);
+        }
+        PaintOutcome::Continue
+    }
+}
+
+/// Print the frame rate and the mean upload time of a framebuffer built by `builder`.
+fn measure(label: &str, builder: FramebufferBuilder<[u8; 4]>) -> anyhow::Result<()> {
+    let mut framebuffer = builder.size(WIDTH, HEIGHT).build_headless()?;
+    for _ in 0..WARM_UP {
+        framebuffer.update(&mut MovingBar)?;
+    }
+
+    let mut upload = Duration::ZERO;
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        framebuffer.update(&mut MovingBar)?;
+        upload += framebuffer.last_metrics().upload;
+    }
+    framebuffer.read_pixels()?;
+    let elapsed = start.elapsed();
+
+    println!(
+        "{label:<22} {:6.1} frames/s, {:5.2} ms upload per frame",
+        FRAMES as f64 / elapsed.as_secs_f64(),
+        upload.as_secs_f64() * 1000.0 / FRAMES as f64,
+    );
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    println!("{WIDTH}x{HEIGHT}, {FRAMES} frames per run");
+    for pbo_ring in 1..=4 {
+        measure(
+            &format!("pbo_ring({pbo_ring})"),
+            FramebufferBuilder::new().pbo_ring(pbo_ring),
+        )?;
+    }
+    for orphan in [false, true] {
+        measure(
+            &format!("orphan_buffers({orphan})"),
+            FramebufferBuilder::new().orphan_buffers(orphan),
+        )?;
+    }
+    Ok(())
+}
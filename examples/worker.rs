@@ -0,0 +1,84 @@
+//! Paints frames on a worker thread and presents them on the main thread.
+use cpf::{frame_channel, FrameReceiver, Framebuffer};
+use std::time::Instant;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 480;
+
+/// Expanding rings, computed as if they were expensive.
+fn paint(pixels: &mut [[u8; 4]], t: f32) {
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let x = (index % WIDTH) as f32 - WIDTH as f32 / 2.0;
+        let y = (index / WIDTH) as f32 - HEIGHT as f32 / 2.0;
+        let value = (((x * x + y * y).sqrt() / 8.0 - t * 4.0).sin() * 127.5 + 127.5) as u8;
+        *pixel = [value, value / 2, 255 - value, 255];
+    }
+}
+
+struct App {
+    frames: FrameReceiver<[u8; 4]>,
+    surface: Option<(winit::window::Window, Framebuffer<[u8; 4]>)>,
+}
+
+impl winit::application::ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            match Framebuffer::init(event_loop, WIDTH, HEIGHT) {
+                Ok(surface) => self.surface = Some(surface),
+                Err(err) => {
+                    eprintln!("failed to initialize the framebuffer: {err}");
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let Some((window, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            winit::event::WindowEvent::RedrawRequested => {
+                if let Some(frame) = self.frames.latest() {
+                    framebuffer.present_slice(&frame).unwrap();
+                    self.frames.recycle(frame);
+                }
+                window.request_redraw();
+            }
+            // the worker paints a fixed size, only scale it to the window
+            winit::event::WindowEvent::Resized(size) => {
+                framebuffer.resize_surface(size.width as usize, size.height as usize);
+            }
+            winit::event::WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let (sender, frames) = frame_channel();
+    std::thread::spawn(move || {
+        let start = Instant::now();
+        loop {
+            let mut frame = sender.buffer(WIDTH * HEIGHT, [0; 4]);
+            paint(&mut frame, start.elapsed().as_secs_f32());
+            if sender.send(frame).is_err() {
+                // the window was closed
+                break;
+            }
+        }
+    });
+
+    let mut app = App {
+        frames,
+        surface: None,
+    };
+    let event_loop = EventLoop::new()?;
+    Ok(event_loop.run_app(&mut app)?)
+}
@@ -0,0 +1,68 @@
+//! Pseudo-color palettes for showing scalar data, e.g. depth or simulation values.
+//!
+//! ```
+//! use cpf::colormap::Colormap;
+//!
+//! let heights = [0.0, 0.5, 1.0];
+//! let pixels: Vec<[u8; 4]> = heights.iter().map(|&h| Colormap::Viridis.sample(h)).collect();
+//! assert_eq!(pixels[0], [0x44, 0x01, 0x54, 255]);
+//! ```
+
+/// Named palette mapping values from 0 to 1 to opaque colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Black to white.
+    Grayscale,
+    /// Perceptually uniform dark blue over green to yellow, matplotlib's default.
+    #[default]
+    Viridis,
+    /// Perceptually uniform black over purple and orange to pale yellow.
+    Inferno,
+    /// Blue over cyan, yellow and red, common but not perceptually uniform.
+    Jet,
+}
+
+impl Colormap {
+    /// Color at `t`, where 0 and 1 are the ends of the palette.
+    ///
+    /// Values outside of `[0, 1]` are clamped, NaN maps to 0.
+    pub fn sample(self, t: f32) -> [u8; 4] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let [r, g, b] = match self {
+            Colormap::Grayscale => [t; 3],
+            Colormap::Viridis => interpolate(&VIRIDIS, t),
+            Colormap::Inferno => interpolate(&INFERNO, t),
+            // piecewise linear ramps peaking at 3/4, 1/2 and 1/4
+            Colormap::Jet => [3.0, 2.0, 1.0].map(|peak| (1.5 - (4.0 * t - peak).abs()).min(1.0)),
+        }
+        .map(|channel| (channel.max(0.0) * 255.0).round() as u8);
+        [r, g, b, 255]
+    }
+
+    /// Color of `value` in the range from `min` to `max`, see [`Colormap::sample`].
+    pub fn sample_range(self, value: f32, min: f32, max: f32) -> [u8; 4] {
+        self.sample((value - min) / (max - min))
+    }
+}
+
+/// Linear interpolation between evenly spaced `stops`, `t` in `[0, 1]`.
+fn interpolate(stops: &[u32], t: f32) -> [f32; 3] {
+    let position = t * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f32;
+    let [low, high] = [stops[index], stops[index + 1]].map(|stop| {
+        let [_, r, g, b] = stop.to_be_bytes();
+        [r, g, b].map(|channel| channel as f32 / 255.0)
+    });
+    std::array::from_fn(|channel| low[channel] + (high[channel] - low[channel]) * fraction)
+}
+
+// matplotlib's palettes at every tenth
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151,
+    0xbddf26, 0xfde725,
+];
+const INFERNO: [u32; 11] = [
+    0x000004, 0x160b39, 0x420a68, 0x6a176e, 0x932667, 0xbc3754, 0xdd513a, 0xf37819, 0xfca50a,
+    0xf6d746, 0xfcffa4,
+];
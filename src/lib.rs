@@ -1,425 +1,4642 @@
 use core::str;
-use std::{ffi::c_void, marker::PhantomData};
+use std::{
+    cell::Cell,
+    cmp::Reverse,
+    collections::VecDeque,
+    ffi::c_void,
+    marker::PhantomData,
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
+};
 
+pub use glow;
 use glow::HasContext;
 use glutin::{
-    config::{Config, ConfigTemplateBuilder, GlConfig as _},
-    context::ContextAttributesBuilder,
+    config::{ColorBufferType, Config, ConfigTemplateBuilder, GetGlConfig as _, GlConfig as _},
+    context::{ContextApi, ContextAttributes, ContextAttributesBuilder, GlProfile, Version},
     display::GetGlDisplay as _,
-    prelude::{GlDisplay as _, NotCurrentGlContext as _},
-    surface::{GlSurface as _, WindowSurface},
+    prelude::{GlDisplay, NotCurrentGlContext as _, PossiblyCurrentGlContext as _},
+    surface::{GlSurface as _, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
 };
 use glutin_winit::{DisplayBuilder, GlWindow};
-use raw_window_handle::HasWindowHandle;
-use winit::dpi::PhysicalSize;
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::{event_loop::ActiveEventLoop, window::Window};
 
+/// When set, [`check_gl`] panics on the first OpenGL error instead of returning it.
+static STRICT_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Forward to the macro of the given level in the `log` crate, or only type check the message
+/// when the `log` feature is disabled.
+macro_rules! log {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::$level!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+/// Log OpenGL errors raised by the preceding calls.
 macro_rules! check {
     // () => {};
     ($gl:expr) => {{
-        let (file, line) = (file!(), line!());
-        // Check for OpenGL errors
-        let err = $gl.get_error();
-        if err != glow::NO_ERROR {
-            eprintln!(
-                "OpenGL Error ({}): {} at {}:{}",
-                err,
-                match err {
-                    glow::INVALID_ENUM => "GL_INVALID_ENUM",
-                    glow::INVALID_VALUE => "GL_INVALID_VALUE",
-                    glow::INVALID_OPERATION => "GL_INVALID_OPERATION",
-                    glow::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
-                    glow::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
-                    glow::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
-                    glow::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
-                    _ => "Unknown Error",
-                },
-                file,
-                line
-            );
+        if let Err(err) = $crate::check_gl(&$gl, concat!(file!(), ":", line!())) {
+            log!(debug, "{}", err);
         }
     }};
 }
 
-struct PixelBuffer<Format> {
-    raw_buffer: glow::Buffer,
-    length: usize,
-    format: PhantomData<Format>,
+/// Propagate OpenGL errors raised by the preceding calls with `?`.
+macro_rules! try_check {
+    ($gl:expr) => {
+        $crate::check_gl(&$gl, concat!(file!(), ":", line!()))?
+    };
 }
 
-struct MMap<'ctx, 'buffer, Format> {
-    buffer: &'buffer PixelBuffer<Format>,
-    gl: &'ctx glow::Context,
-    mapped_memory: *mut c_void,
-}
+// after the macros, which they use
+pub mod colormap;
+mod run;
+#[cfg(feature = "software")]
+pub mod soft;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "ui")]
+pub mod ui;
+pub mod util;
 
-impl<Format> AsMut<[Format]> for MMap<'_, '_, Format> {
-    fn as_mut(&mut self) -> &mut [Format] {
-        unsafe {
-            std::slice::from_raw_parts_mut(self.mapped_memory as *mut Format, self.buffer.length)
-        }
-    }
-}
+pub use run::{run_painter, run_painter_limited};
 
-impl<'ctx, 'buffer, Format> MMap<'ctx, 'buffer, Format> {
-    // Constructor for creating the guard
-    pub fn new(gl: &'ctx glow::Context, buffer: &'buffer PixelBuffer<Format>) -> Self {
-        let mapped_memory;
-        unsafe {
-            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(buffer.raw_buffer));
-            check!(gl);
+/// Kind of error reported by `glGetError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlErrorKind {
+    InvalidEnum,
+    InvalidValue,
+    InvalidOperation,
+    StackOverflow,
+    StackUnderflow,
+    OutOfMemory,
+    InvalidFramebufferOperation,
+    /// The context was lost, e.g. in a driver reset, see [`Framebuffer::reinit`].
+    ContextLost,
+    Unknown(u32),
+}
 
-            // map the buffer to client memory
-            mapped_memory = gl.map_buffer_range(
-                glow::PIXEL_UNPACK_BUFFER,
-                0,
-                (buffer.length * std::mem::size_of::<Format>()) as _,
-                glow::MAP_READ_BIT | glow::MAP_WRITE_BIT,
-            ) as *mut c_void;
-            check!(gl);
+impl GlErrorKind {
+    fn from_code(code: u32) -> Self {
+        match code {
+            glow::INVALID_ENUM => GlErrorKind::InvalidEnum,
+            glow::INVALID_VALUE => GlErrorKind::InvalidValue,
+            glow::INVALID_OPERATION => GlErrorKind::InvalidOperation,
+            glow::STACK_OVERFLOW => GlErrorKind::StackOverflow,
+            glow::STACK_UNDERFLOW => GlErrorKind::StackUnderflow,
+            glow::OUT_OF_MEMORY => GlErrorKind::OutOfMemory,
+            glow::INVALID_FRAMEBUFFER_OPERATION => GlErrorKind::InvalidFramebufferOperation,
+            glow::CONTEXT_LOST => GlErrorKind::ContextLost,
+            code => GlErrorKind::Unknown(code),
         }
+    }
 
-        if mapped_memory.is_null() {
-            panic!("failed to map buffer to client memory");
+    /// Raw error code as returned by `glGetError`.
+    pub fn code(self) -> u32 {
+        match self {
+            GlErrorKind::InvalidEnum => glow::INVALID_ENUM,
+            GlErrorKind::InvalidValue => glow::INVALID_VALUE,
+            GlErrorKind::InvalidOperation => glow::INVALID_OPERATION,
+            GlErrorKind::StackOverflow => glow::STACK_OVERFLOW,
+            GlErrorKind::StackUnderflow => glow::STACK_UNDERFLOW,
+            GlErrorKind::OutOfMemory => glow::OUT_OF_MEMORY,
+            GlErrorKind::InvalidFramebufferOperation => glow::INVALID_FRAMEBUFFER_OPERATION,
+            GlErrorKind::ContextLost => glow::CONTEXT_LOST,
+            GlErrorKind::Unknown(code) => code,
         }
+    }
 
-        MMap {
-            buffer,
-            mapped_memory,
-            gl,
+    fn name(self) -> &'static str {
+        match self {
+            GlErrorKind::InvalidEnum => "GL_INVALID_ENUM",
+            GlErrorKind::InvalidValue => "GL_INVALID_VALUE",
+            GlErrorKind::InvalidOperation => "GL_INVALID_OPERATION",
+            GlErrorKind::StackOverflow => "GL_STACK_OVERFLOW",
+            GlErrorKind::StackUnderflow => "GL_STACK_UNDERFLOW",
+            GlErrorKind::OutOfMemory => "GL_OUT_OF_MEMORY",
+            GlErrorKind::InvalidFramebufferOperation => "GL_INVALID_FRAMEBUFFER_OPERATION",
+            GlErrorKind::ContextLost => "GL_CONTEXT_LOST",
+            GlErrorKind::Unknown(_) => "Unknown Error",
         }
     }
 }
 
-impl<Format> Drop for MMap<'_, '_, Format> {
-    fn drop(&mut self) {
-        unsafe {
-            self.gl
-                .bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(self.buffer.raw_buffer));
-            // this will sync the data with the GPU
-            self.gl.unmap_buffer(glow::PIXEL_UNPACK_BUFFER);
-            check!(self.gl);
-        }
+/// OpenGL error together with the place it was detected at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlError {
+    pub kind: GlErrorKind,
+    pub context: &'static str,
+}
+
+impl std::fmt::Display for GlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "OpenGL Error ({}): {} at {}",
+            self.kind.code(),
+            self.kind.name(),
+            self.context
+        )
     }
 }
 
-pub struct Framebuffer<PixelFormat> {
-    // window surface handles
-    surface: glutin::surface::Surface<WindowSurface>,
-    ctx_handle: glutin::context::PossiblyCurrentContext,
+impl std::error::Error for GlError {}
 
-    // opengl state
-    width: usize,
-    height: usize,
-    gl: glow::Context,
-    pixel_buffer: PixelBuffer<PixelFormat>,
-    texture: glow::Texture,
-    vao: glow::VertexArray,
-    program: glow::Program,
+/// Check for errors raised by the preceding OpenGL calls.
+///
+/// In strict mode (see [`set_strict_errors`]) errors panic instead.
+fn check_gl(gl: &glow::Context, context: &'static str) -> Result<(), GlError> {
+    let code = unsafe { gl.get_error() };
+    if code == glow::NO_ERROR {
+        return Ok(());
+    }
+
+    let err = GlError {
+        kind: GlErrorKind::from_code(code),
+        context,
+    };
+    if STRICT_ERRORS.load(Ordering::Relaxed) {
+        panic!("{}", err);
+    }
+    Err(err)
 }
 
-pub struct FramebufferConfigExt {
-    pub clear_color: Option<[f32; 4]>,
+/// Escalate OpenGL errors to panics, reporting the file and line of the failing call.
+///
+/// Intended for development, e.g. while writing custom shaders where errors otherwise just
+/// produce a blank screen. The setting is global: it applies to every framebuffer in the
+/// process, including ones created later, as errors are also checked in places that have no
+/// access to a particular framebuffer.
+pub fn set_strict_errors(strict: bool) {
+    STRICT_ERRORS.store(strict, Ordering::Relaxed);
 }
 
-impl<Format> Framebuffer<Format> {
-    fn gl_config_picker(configs: Box<dyn Iterator<Item = Config> + '_>) -> Config {
-        configs
-            .reduce(|accum, config| {
-                let transparency_check = config.supports_transparency().unwrap_or(false)
-                    & !accum.supports_transparency().unwrap_or(false);
-
-                if transparency_check || config.num_samples() < accum.num_samples() {
-                    config
-                } else {
-                    accum
-                }
-            })
-            .unwrap()
+/// `length` pixels with all bytes zeroed, to read pixels from OpenGL into.
+fn zeroed_pixels<Format: PixelFormat>(length: usize) -> Vec<Format> {
+    let mut pixels = Vec::with_capacity(length);
+    // PixelFormat makes every bit pattern, including all zeroes, a valid pixel
+    unsafe {
+        std::ptr::write_bytes(pixels.as_mut_ptr(), 0, length);
+        pixels.set_len(length);
     }
+    pixels
+}
 
-    #[allow(unused)]
-    pub fn init(
-        event_loop: &ActiveEventLoop,
-        width: usize,
-        height: usize,
-    ) -> (winit::window::Window, Self) {
-        Self::init_with_ext(event_loop, width, height, None)
+/// The bytes of `pixels`, as written by OpenGL.
+fn pixel_bytes_mut<Format: PixelFormat>(pixels: &mut [Format]) -> &mut [u8] {
+    // PixelFormat makes pixels plain data, so any bytes written form valid pixels
+    unsafe {
+        std::slice::from_raw_parts_mut(
+            pixels.as_mut_ptr() as *mut u8,
+            std::mem::size_of_val(pixels),
+        )
     }
+}
 
-    pub fn init_with_ext(
-        event_loop: &ActiveEventLoop,
-        width: usize,
-        height: usize,
-        ext: Option<FramebufferConfigExt>,
-    ) -> (winit::window::Window, Self) {
-        let size = width * height;
+/// Layout of a single pixel as uploaded to OpenGL.
+///
+/// # Safety
+///
+/// Implementors must be plain data whose size and memory layout exactly match a pixel described
+/// by `GL_FORMAT` and `GL_TYPE`, since pixel buffers are reinterpreted as slices of `Self`.
+/// Every bit pattern of that size must be a valid value, as pixels read back from OpenGL hold
+/// whatever bytes it wrote.
+pub unsafe trait PixelFormat: Copy {
+    /// Pixel data format passed to OpenGL, e.g. `glow::RGBA`.
+    const GL_FORMAT: u32;
+    /// Data type of each channel, e.g. `glow::UNSIGNED_BYTE`.
+    const GL_TYPE: u32;
+    /// Sized internal format of the texture the pixels are uploaded to, unless overridden with
+    /// [`FramebufferBuilder::internal_format`].
+    ///
+    /// Should hold every bit of the pixels without wasting memory, e.g. `glow::RGBA8` for 8 bit
+    /// channels.
+    const INTERNAL_FORMAT: u32;
+    /// Internal format decoding sRGB encoded pixels to linear values when sampled, used instead
+    /// of `INTERNAL_FORMAT` by [`FramebufferBuilder::srgb`] framebuffers.
+    ///
+    /// `None` for formats holding linear values, which are then uploaded as is.
+    const SRGB_INTERNAL_FORMAT: Option<u32> = None;
+    /// Number of channels in a pixel.
+    const CHANNELS: usize;
+    /// Texture swizzle mapping the uploaded channels to the RGBA channels sampled by the shader.
+    const SWIZZLE: [u32; 4] = [glow::RED, glow::GREEN, glow::BLUE, glow::ALPHA];
 
-        let (window, surface, ctx_handle, gl) = {
-            let window_attributes = Window::default_attributes().with_inner_size(PhysicalSize {
-                width: width as u32,
-                height: height as u32,
-            });
+    /// Convert to 8 bit RGBA, e.g. for encoding images.
+    fn to_rgba8(self) -> [u8; 4];
 
-            let template = ConfigTemplateBuilder::new().with_alpha_size(8);
+    /// Convert from 8 bit RGBA, dropping the channels the format doesn't have.
+    ///
+    /// Together with [`PixelFormat::to_rgba8`] this converts between any two formats, see
+    /// [`util::convert_into`].
+    fn from_rgba8(rgba: [u8; 4]) -> Self;
+}
 
-            let display_builder =
-                DisplayBuilder::new().with_window_attributes(Some(window_attributes));
+unsafe impl PixelFormat for [u8; 4] {
+    const GL_FORMAT: u32 = glow::RGBA;
+    const GL_TYPE: u32 = glow::UNSIGNED_BYTE;
+    const INTERNAL_FORMAT: u32 = glow::RGBA8;
+    const SRGB_INTERNAL_FORMAT: Option<u32> = Some(glow::SRGB8_ALPHA8);
+    const CHANNELS: usize = 4;
 
-            let (window, gl_config) = display_builder
-                .build(event_loop, template, Self::gl_config_picker)
-                .unwrap();
+    fn to_rgba8(self) -> [u8; 4] {
+        self
+    }
 
-            let window = window.expect("can create OpenGL window");
-            let display = gl_config.display();
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        rgba
+    }
+}
 
-            let surface = {
-                let attrs = window
-                    .build_surface_attributes(<_>::default())
-                    .expect("Failed to build surface attributes");
-                unsafe { display.create_window_surface(&gl_config, &attrs).unwrap() }
-            };
+unsafe impl PixelFormat for [u8; 3] {
+    const GL_FORMAT: u32 = glow::RGB;
+    const GL_TYPE: u32 = glow::UNSIGNED_BYTE;
+    const INTERNAL_FORMAT: u32 = glow::RGB8;
+    const SRGB_INTERNAL_FORMAT: Option<u32> = Some(glow::SRGB8);
+    const CHANNELS: usize = 3;
 
-            let ctx_handle = {
-                let raw_window_handle = window
-                    .window_handle()
-                    .expect("can obtain a raw window handle")
-                    .as_raw();
+    fn to_rgba8(self) -> [u8; 4] {
+        let [r, g, b] = self;
+        [r, g, b, 255]
+    }
 
-                let context_attributes =
-                    ContextAttributesBuilder::new().build(Some(raw_window_handle));
+    fn from_rgba8([r, g, b, _]: [u8; 4]) -> Self {
+        [r, g, b]
+    }
+}
 
-                let not_current_gl_context = unsafe {
-                    display
-                        .create_context(&gl_config, &context_attributes)
-                        .expect("failed to create context")
-                };
+unsafe impl PixelFormat for [f32; 4] {
+    const GL_FORMAT: u32 = glow::RGBA;
+    const GL_TYPE: u32 = glow::FLOAT;
+    const INTERNAL_FORMAT: u32 = glow::RGBA32F;
+    const CHANNELS: usize = 4;
 
-                not_current_gl_context
-                    .make_current(&surface)
-                    .expect("can make context current")
-            };
+    fn to_rgba8(self) -> [u8; 4] {
+        self.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
 
-            let gl = unsafe {
-                glow::Context::from_loader_function_cstr(|s| display.get_proc_address(s))
-            };
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        rgba.map(|channel| channel as f32 / 255.0)
+    }
+}
 
-            (window, surface, ctx_handle, gl)
-        };
+/// 16 bits per channel, e.g. for scientific or medical images.
+///
+/// The texture keeps all 16 bits, so [`Framebuffer::read_pixels`] returns the pixels as they
+/// were uploaded, while the window shows as many as the display can.
+unsafe impl PixelFormat for [u16; 4] {
+    const GL_FORMAT: u32 = glow::RGBA;
+    const GL_TYPE: u32 = glow::UNSIGNED_SHORT;
+    const INTERNAL_FORMAT: u32 = glow::RGBA16;
+    const CHANNELS: usize = 4;
 
-        let program = {
-            let vertex_shader =
-                Self::compile_shader(&gl, Self::VERTEX_SHADER_SRC, glow::VERTEX_SHADER);
-            let fragment_shader =
-                Self::compile_shader(&gl, Self::FRAGMENT_SHADER_SRC, glow::FRAGMENT_SHADER);
-            Self::create_shader_program(&gl, vertex_shader, fragment_shader)
-        };
+    fn to_rgba8(self) -> [u8; 4] {
+        self.map(|channel| (channel >> 8) as u8)
+    }
 
-        let vao;
-        let pixel_buffer;
-        let texture;
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        // 0xab becomes 0xabab, mapping 255 to the maximum
+        rgba.map(|channel| channel as u16 * 257)
+    }
+}
 
-        unsafe {
-            vao = gl.create_vertex_array().unwrap();
-            check!(gl);
-            gl.bind_vertex_array(Some(vao));
-            check!(gl);
-            // Create the PBO and the texture
-            pixel_buffer = Self::create_pixel_buffer(&gl, size);
-            check!(gl);
-            texture = Self::create_texture(&gl, width, height);
-            check!(gl);
-        }
+/// Single channel grayscale.
+unsafe impl PixelFormat for u8 {
+    const GL_FORMAT: u32 = glow::RED;
+    const GL_TYPE: u32 = glow::UNSIGNED_BYTE;
+    const INTERNAL_FORMAT: u32 = glow::R8;
+    const CHANNELS: usize = 1;
+    // broadcast red to gray instead of showing it as red
+    const SWIZZLE: [u32; 4] = [glow::RED, glow::RED, glow::RED, glow::ONE];
 
-        unsafe {
-            let [r, g, b, a] = ext
-                .map(|ext| ext.clear_color.unwrap_or_default())
-                .unwrap_or_default();
+    fn to_rgba8(self) -> [u8; 4] {
+        [self, self, self, 255]
+    }
 
-            gl.clear_color(r, g, b, a);
-            check!(gl);
-        }
+    /// Luma of the color with the Rec. 709 weights.
+    fn from_rgba8([r, g, b, _]: [u8; 4]) -> Self {
+        ((54 * r as u32 + 183 * g as u32 + 19 * b as u32 + 128) >> 8) as u8
+    }
+}
 
-        (
-            window,
-            Self {
-                surface,
-                ctx_handle,
-                width,
-                height,
-                gl,
-                pixel_buffer,
-                texture,
-                vao,
-                program,
-            },
-        )
+/// 8 bit per channel pixel stored in blue, green, red, alpha order.
+///
+/// Matches the native byte order of many image sources on Windows, the swap to RGBA is done by
+/// OpenGL during the upload instead of by the painter.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bgra8(pub [u8; 4]);
+
+unsafe impl PixelFormat for Bgra8 {
+    const GL_FORMAT: u32 = glow::BGRA;
+    const GL_TYPE: u32 = glow::UNSIGNED_BYTE;
+    const INTERNAL_FORMAT: u32 = glow::RGBA8;
+    const SRGB_INTERNAL_FORMAT: Option<u32> = Some(glow::SRGB8_ALPHA8);
+    const CHANNELS: usize = 4;
+
+    fn to_rgba8(self) -> [u8; 4] {
+        let Bgra8([b, g, r, a]) = self;
+        [r, g, b, a]
     }
 
-    fn compile_shader(gl: &glow::Context, source: &str, shader_type: u32) -> glow::Shader {
-        unsafe {
-            let shader = gl.create_shader(shader_type).unwrap();
-            gl.shader_source(shader, source);
-            gl.compile_shader(shader);
+    fn from_rgba8([r, g, b, a]: [u8; 4]) -> Self {
+        Bgra8([b, g, r, a])
+    }
+}
 
-            if !gl.get_shader_compile_status(shader) {
-                let error_msg = gl.get_shader_info_log(shader);
-                println!("ERROR::SHADER::COMPILATION_FAILED\n{}", error_msg);
+struct PixelBuffer<Format> {
+    raw_buffer: glow::Buffer,
+    length: usize,
+    format: PhantomData<Format>,
+    // memory mapped for the lifetime of the buffer, see `FramebufferConfigExt::persistent_mapping`
+    persistent: Option<*mut c_void>,
+    // signaled once the GPU no longer reads a persistently mapped buffer
+    fence: Cell<Option<glow::Fence>>,
+}
+
+impl<Format> PixelBuffer<Format> {
+    /// Wait until the GPU no longer reads a persistently mapped buffer.
+    fn wait_for_upload(&self, gl: &glow::Context) -> Result<(), FramebufferError> {
+        if let Some(fence) = self.fence.take() {
+            unsafe {
+                while gl.client_wait_sync(fence, glow::SYNC_FLUSH_COMMANDS_BIT, i32::MAX)
+                    == glow::TIMEOUT_EXPIRED
+                {}
+                try_check!(gl);
+                gl.delete_sync(fence);
             }
-            shader
         }
+        Ok(())
     }
 
-    fn create_shader_program(
-        gl: &glow::Context,
-        vertex_shader: glow::Shader,
-        fragment_shader: glow::Shader,
-    ) -> glow::Program {
-        unsafe {
-            let program = gl.create_program().unwrap();
-            gl.attach_shader(program, vertex_shader);
-            gl.attach_shader(program, fragment_shader);
-            gl.link_program(program);
-
-            if !gl.get_program_link_status(program) {
-                println!(
-                    "ERROR::PROGRAM::LINKING_FAILED\n{}",
-                    gl.get_program_info_log(program)
-                );
+    /// Remember when the GPU is done with an upload from a persistently mapped buffer.
+    fn fence_upload(&self, gl: &glow::Context) {
+        if self.persistent.is_some() {
+            unsafe {
+                match gl.fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0) {
+                    Ok(fence) => self.fence.set(Some(fence)),
+                    Err(err) => log!(error, "failed to create fence: {err}"),
+                }
             }
-            program
         }
     }
 
-    const VERTEX_SHADER_SRC: &str = r#"
-#version 330 core
-out vec2 TexCoord;
+    fn delete(&self, gl: &glow::Context) {
+        unsafe {
+            if let Some(fence) = self.fence.take() {
+                gl.delete_sync(fence);
+            }
+            // deleting the buffer also unmaps it
+            gl.delete_buffer(self.raw_buffer);
+        }
+    }
+}
 
-void main()
-{
-    // Define the quad vertices directly
-    vec3 vertices[4] = vec3[4] (
-        vec3(-1.0, -1.0, 0.0),  // bottom-left  (0)
-        vec3( 1.0, -1.0, 0.0),  // bottom-right (1)
-        vec3(-1.0,  1.0, 0.0),  // top-left     (2)
-        vec3( 1.0,  1.0, 0.0)   // top-right    (3)
-    );
+/// Pixels of a [`Framebuffer`] mapped to client memory, obtained with [`Framebuffer::map`].
+///
+/// The guard mutably borrows the framebuffer, so it can not be drawn while the pixels are
+/// mapped. Dropping the guard unmaps the buffer and uploads its contents to the texture.
+///
+/// The borrow also rules out mapping twice at once:
+///
+/// ```compile_fail
+/// # fn map(framebuffer: &mut cpf::Framebuffer<[u8; 4]>) -> Result<(), cpf::FramebufferError> {
+/// let first = framebuffer.map()?;
+/// let second = framebuffer.map()?;
+/// # drop(first);
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "dropping the guard immediately uploads the pixel buffer"]
+pub struct MMap<'fb, Format: PixelFormat> {
+    // shared, but obtained from a mutable borrow held for the guard's lifetime, so no other
+    // guard can map a pixel buffer at the same time
+    framebuffer: &'fb Framebuffer<Format>,
+    buffer: &'fb PixelBuffer<Format>,
+    // start of the mapped pixels, which need not be the start of the buffer
+    mapped_memory: *mut c_void,
+    // number of mapped pixels
+    length: usize,
+    // mapped with `GL_MAP_FLUSH_EXPLICIT_BIT`, the writes are flushed before unmapping
+    flush_explicit: bool,
+    // region `[x, y, width, height]` to upload, the whole frame if `None`
+    dirty_rect: Option<[usize; 4]>,
+}
 
-    // Texture coordinates corresponding to each vertex
-    vec2 texCoords[4] = vec2[4](
-        vec2(0.0, 1.0),
-        vec2(1.0, 1.0),
-        vec2(0.0, 0.0),
-        vec2(1.0, 0.0) 
-    );
+impl<Format: PixelFormat> std::ops::Deref for MMap<'_, Format> {
+    type Target = [Format];
 
-    // Set gl_Position and pass the texture coordinates
-    gl_Position = vec4(vertices[gl_VertexID].xyz, 1.0);
-    TexCoord = texCoords[gl_VertexID];
+    fn deref(&self) -> &[Format] {
+        unsafe { std::slice::from_raw_parts(self.mapped_memory as *const Format, self.length) }
+    }
 }
-"#;
 
-    const FRAGMENT_SHADER_SRC: &str = r#"
-#version 330 core
-out vec4 FragColor;
-in vec2 TexCoord;
-uniform sampler2D texture1;
-void main()
-{
-    FragColor = texture(texture1, TexCoord);
+impl<Format: PixelFormat> std::ops::DerefMut for MMap<'_, Format> {
+    fn deref_mut(&mut self) -> &mut [Format] {
+        unsafe { std::slice::from_raw_parts_mut(self.mapped_memory as *mut Format, self.length) }
+    }
 }
-"#;
 
-    fn create_pixel_buffer(gl: &glow::Context, length: usize) -> PixelBuffer<Format> {
-        unsafe {
-            let pbo = gl.create_buffer().unwrap();
-            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(pbo));
-            check!(gl);
-            gl.buffer_data_size(
-                glow::PIXEL_UNPACK_BUFFER,
-                (length * std::mem::size_of::<Format>()) as _,
-                glow::STREAM_DRAW,
-            );
-            check!(gl);
-            PixelBuffer {
-                raw_buffer: pbo,
-                length,
-                format: PhantomData,
-            }
-        }
+impl<Format: PixelFormat> AsRef<[Format]> for MMap<'_, Format> {
+    fn as_ref(&self) -> &[Format] {
+        self
     }
+}
 
-    fn create_texture(gl: &glow::Context, width: usize, height: usize) -> glow::Texture {
-        unsafe {
-            let texture = gl.create_texture().unwrap();
-            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
-            check!(gl);
-            gl.texture_storage_2d(texture, 1, glow::RGBA32F, width as i32, height as i32);
-            check!(gl);
-            gl.texture_parameter_i32(texture, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
-            check!(gl);
-            gl.texture_parameter_i32(texture, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
-            check!(gl);
-            texture
-        }
+impl<Format: PixelFormat> AsMut<[Format]> for MMap<'_, Format> {
+    fn as_mut(&mut self) -> &mut [Format] {
+        self
     }
 }
 
-impl Framebuffer<[u8; 4]> {
-    pub fn draw(&self, painter: &mut impl Painter<Pixel = [u8; 4]>) {
-        let gl = &self.gl;
+impl<'fb, Format: PixelFormat> MMap<'fb, Format> {
+    // Constructor for creating the guard
+    fn new(framebuffer: &'fb mut Framebuffer<Format>) -> Result<Self, FramebufferError> {
+        Self::with_access(framebuffer, glow::MAP_READ_BIT | glow::MAP_WRITE_BIT, false)
+    }
 
-        unsafe {
-            gl.clear(glow::COLOR_BUFFER_BIT);
-            check!(gl);
-        }
+    /// Map only the `length` pixels from `offset` on for writing, see [`Framebuffer::map_rect`].
+    ///
+    /// The writes are flushed explicitly on drop, so the driver only has to synchronize the
+    /// mapped range.
+    fn new_range(
+        framebuffer: &'fb mut Framebuffer<Format>,
+        offset: usize,
+        length: usize,
+    ) -> Result<Self, FramebufferError> {
+        Self::with_range(
+            framebuffer,
+            offset..offset + length,
+            glow::MAP_WRITE_BIT | glow::MAP_INVALIDATE_RANGE_BIT | glow::MAP_FLUSH_EXPLICIT_BIT,
+            false,
+        )
+    }
 
-        // repaint the window - note: scope here is important as it unmaps pixel buffer from CPU memory
-        {
-            let mut guard = MMap::new(gl, &self.pixel_buffer);
+    /// Map for writing only, skipping the slow read back of the previous contents.
+    fn new_write(framebuffer: &'fb mut Framebuffer<Format>) -> Result<Self, FramebufferError> {
+        Self::with_access(
+            framebuffer,
+            glow::MAP_WRITE_BIT | glow::MAP_INVALIDATE_RANGE_BIT,
+            false,
+        )
+    }
 
-            painter.paint(guard.as_mut());
-        }
+    /// Map a fresh allocation instead of the storage the GPU may still be reading from.
+    ///
+    /// Re-specifying the buffer lets the driver hand out new memory right away instead of
+    /// waiting for prior uploads, at the cost of the previous contents.
+    fn orphaned(framebuffer: &'fb mut Framebuffer<Format>) -> Result<Self, FramebufferError> {
+        Self::with_access(framebuffer, glow::MAP_WRITE_BIT, true)
+    }
 
-        unsafe {
-            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
-            check!(gl);
-            gl.bind_buffer(
-                glow::PIXEL_UNPACK_BUFFER,
-                Some(self.pixel_buffer.raw_buffer),
-            );
-            check!(gl);
+    fn with_access(
+        framebuffer: &'fb mut Framebuffer<Format>,
+        access: u32,
+        orphan: bool,
+    ) -> Result<Self, FramebufferError> {
+        let length = framebuffer.pixel_buffers[framebuffer.current_buffer.get()].length;
+        Self::with_range(framebuffer, 0..length, access, orphan)
+    }
 
-            // Transfer data from the PBO to the texture
-            gl.texture_sub_image_2d(
-                self.texture,
-                0, // mip level
-                0, // x offset
-                0, // y offset
-                self.width as _,
-                self.height as _,
-                glow::RGBA,          // TODO: Format needs to provide these values
-                glow::UNSIGNED_BYTE, // TODO: Format needs to provide these values
-                glow::PixelUnpackData::BufferOffset(0),
-            );
-            check!(gl);
+    /// Map the pixels in `range` of the current pixel buffer.
+    fn with_range(
+        framebuffer: &'fb mut Framebuffer<Format>,
+        range: std::ops::Range<usize>,
+        access: u32,
+        orphan: bool,
+    ) -> Result<Self, FramebufferError> {
+        let framebuffer: &'fb Framebuffer<Format> = framebuffer;
+        let gl = &framebuffer.gl;
+        let buffer = &framebuffer.pixel_buffers[framebuffer.current_buffer.get()];
+        let pixel_size = std::mem::size_of::<Format>();
+        let length = range.len();
 
-            gl.use_program(Some(self.program));
-            check!(gl);
-            gl.bind_vertex_array(Some(self.vao));
-            check!(gl);
-            gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
-            check!(gl);
+        if let Some(mapped_memory) = buffer.persistent {
+            // the memory stays mapped, only make sure the GPU is done uploading from it
+            buffer.wait_for_upload(gl)?;
+            return Ok(MMap {
+                framebuffer,
+                buffer,
+                mapped_memory: unsafe { mapped_memory.byte_add(range.start * pixel_size) },
+                length,
+                flush_explicit: false,
+                dirty_rect: None,
+            });
+        }
+        if length == 0 {
+            // mapping an empty range is an error, there is nothing to write anyway
+            return Ok(MMap {
+                framebuffer,
+                buffer,
+                mapped_memory: std::ptr::NonNull::<Format>::dangling().as_ptr() as *mut c_void,
+                length,
+                flush_explicit: false,
+                dirty_rect: Some([0, 0, 0, 0]),
+            });
         }
 
-        self.surface
+        let mapped_memory;
+        unsafe {
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(buffer.raw_buffer));
+            try_check!(gl);
+
+            if orphan {
+                let size = (buffer.length * pixel_size) as _;
+                let usage = framebuffer.buffer_usage.gl_enum();
+                gl.buffer_data_size(glow::PIXEL_UNPACK_BUFFER, size, usage);
+                try_check!(gl);
+            }
+
+            // map the buffer to client memory
+            mapped_memory = gl.map_buffer_range(
+                glow::PIXEL_UNPACK_BUFFER,
+                (range.start * pixel_size) as _,
+                (length * pixel_size) as _,
+                access,
+            ) as *mut c_void;
+            try_check!(gl);
+        }
+
+        if mapped_memory.is_null() {
+            return Err(FramebufferError::BufferMapping);
+        }
+
+        Ok(MMap {
+            framebuffer,
+            buffer,
+            mapped_memory,
+            length,
+            flush_explicit: access & glow::MAP_FLUSH_EXPLICIT_BIT != 0,
+            dirty_rect: None,
+        })
+    }
+
+    /// Upload only the region `[x, y, width, height]` (in pixels) on drop.
+    ///
+    /// The rest of the texture keeps its previous contents, so pixels outside of the region
+    /// need not be written. Rows count from the [`Origin`] and the region is clipped to the
+    /// frame.
+    pub fn set_dirty_rect(&mut self, rect: [usize; 4]) {
+        self.dirty_rect = Some(rect);
+    }
+
+    /// Draw `text` in `color` with its top left corner at (`x`, `y`), see
+    /// [`text::draw_text`]. Needs the `text` feature.
+    ///
+    /// Coordinates are relative to the first mapped row, which for [`Framebuffer::map_rect`] is
+    /// the top of the region.
+    #[cfg(feature = "text")]
+    pub fn draw_text(&mut self, text: &str, x: usize, y: usize, color: Format) {
+        let width = self.framebuffer.width;
+        text::draw_text(self, width, text, x, y, color);
+    }
+}
+
+impl<Format: PixelFormat> Drop for MMap<'_, Format> {
+    fn drop(&mut self) {
+        let gl = &self.framebuffer.gl;
+        unsafe {
+            if self.buffer.persistent.is_some() {
+                // make the writes visible to the upload without unmapping
+                gl.memory_barrier(glow::CLIENT_MAPPED_BUFFER_BARRIER_BIT);
+                check!(gl);
+            } else if self.length > 0 {
+                gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(self.buffer.raw_buffer));
+                if self.flush_explicit {
+                    // relative to the start of the mapped range
+                    let size = self.length * std::mem::size_of::<Format>();
+                    gl.flush_mapped_buffer_range(glow::PIXEL_UNPACK_BUFFER, 0, size as _);
+                    check!(gl);
+                }
+                // this will sync the data with the GPU
+                gl.unmap_buffer(glow::PIXEL_UNPACK_BUFFER);
+                check!(gl);
+            }
+        }
+        self.framebuffer
+            .upload_texture(self.buffer, self.dirty_rect);
+        self.buffer.fence_upload(gl);
+
+        // the GPU copies from this buffer in the background, have the next frame paint into the
+        // next one
+        let current = &self.framebuffer.current_buffer;
+        current.set((current.get() + 1) % self.framebuffer.pixel_buffers.len());
+    }
+}
+
+/// Errors which can occur while setting up a [`Framebuffer`].
+#[derive(Debug)]
+pub enum FramebufferError {
+    /// No OpenGL config matches the requirements or the window could not be created.
+    ConfigSelection(String),
+    /// The window surface could not be created.
+    SurfaceBuild(String),
+    /// The OpenGL context could not be created or made current.
+    ContextCreation(glutin::error::Error),
+    /// A required OpenGL function could not be loaded.
+    ProcAddressLoading(&'static str),
+    /// The context lacks direct state access, which needs OpenGL 4.5 or
+    /// `GL_ARB_direct_state_access`.
+    UnsupportedVersion { major: u32, minor: u32 },
+    /// An OpenGL object (buffer, texture, shader, ...) could not be created.
+    ObjectCreation(String),
+    /// An OpenGL call failed.
+    Gl(GlError),
+    /// The pixel buffer could not be mapped to client memory.
+    BufferMapping,
+    /// A slice of pixels doesn't match the size of the framebuffer.
+    BufferSize { expected: usize, actual: usize },
+    /// Rows of a strided slice of pixels are shorter than the framebuffer is wide.
+    RowLength { row_length: usize, width: usize },
+    /// The texture can't be created with the requested internal format, see
+    /// [`FramebufferBuilder::internal_format`].
+    UnsupportedInternalFormat(u32),
+    /// A pixel coordinate lies outside of the framebuffer.
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// The framebuffer would be empty or larger than the driver's maximum texture size.
+    InvalidSize {
+        width: usize,
+        height: usize,
+        max: u32,
+    },
+    /// Presenting the frame failed.
+    SwapBuffers(glutin::error::Error),
+    /// Encoding or writing an image failed.
+    #[cfg(feature = "png")]
+    Image(image::ImageError),
+    /// The platform rejected the requested swap interval.
+    SwapInterval(glutin::error::Error),
+    /// The OpenGL context was lost, e.g. in a driver reset or GPU switch, and has to be replaced
+    /// with [`Framebuffer::reinit`] before drawing again.
+    ContextLost,
+    /// The operation needs a window surface, which headless framebuffers don't have.
+    Headless,
+    /// A shader failed to compile.
+    Shader(ShaderError),
+    /// The shader program failed to link.
+    Program(ProgramError),
+    /// The event loop of [`run_painter`] could not be created or failed.
+    EventLoop(winit::error::EventLoopError),
+    /// The software fallback failed.
+    ///
+    /// Kept as a message, softbuffer's error is not `Send`.
+    #[cfg(feature = "software")]
+    Software(String),
+}
+
+impl From<GlError> for FramebufferError {
+    fn from(err: GlError) -> Self {
+        FramebufferError::Gl(err)
+    }
+}
+
+impl From<ShaderError> for FramebufferError {
+    fn from(err: ShaderError) -> Self {
+        FramebufferError::Shader(err)
+    }
+}
+
+impl From<ProgramError> for FramebufferError {
+    fn from(err: ProgramError) -> Self {
+        FramebufferError::Program(err)
+    }
+}
+
+/// Pipeline stage of a shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+impl ShaderStage {
+    fn gl_enum(self) -> u32 {
+        match self {
+            ShaderStage::Vertex => glow::VERTEX_SHADER,
+            ShaderStage::Fragment => glow::FRAGMENT_SHADER,
+        }
+    }
+}
+
+/// A shader failed to compile.
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    pub stage: ShaderStage,
+    /// Info log of the compiler.
+    pub log: String,
+}
+
+impl std::fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stage = match self.stage {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::Fragment => "fragment",
+        };
+        write!(f, "failed to compile {stage} shader: {}", self.log)
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// The shader program failed to link.
+#[derive(Debug, Clone)]
+pub struct ProgramError {
+    /// Info log of the linker.
+    pub log: String,
+}
+
+impl std::fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to link shader program: {}", self.log)
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+impl std::fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FramebufferError::ConfigSelection(err) => {
+                write!(f, "failed to select an OpenGL config: {err}")
+            }
+            FramebufferError::SurfaceBuild(err) => {
+                write!(f, "failed to build the window surface: {err}")
+            }
+            FramebufferError::ContextCreation(err) => {
+                write!(f, "failed to create the OpenGL context: {err}")
+            }
+            FramebufferError::ProcAddressLoading(name) => {
+                write!(f, "failed to load OpenGL function {name}")
+            }
+            FramebufferError::UnsupportedVersion { major, minor } => write!(
+                f,
+                "OpenGL {major}.{minor} lacks direct state access, which needs OpenGL 4.5 or \
+                 GL_ARB_direct_state_access"
+            ),
+            FramebufferError::ObjectCreation(err) => {
+                write!(f, "failed to create an OpenGL object: {err}")
+            }
+            FramebufferError::Gl(err) => err.fmt(f),
+            FramebufferError::BufferMapping => {
+                write!(f, "failed to map the pixel buffer to client memory")
+            }
+            FramebufferError::BufferSize { expected, actual } => {
+                write!(f, "expected {expected} pixels, got {actual}")
+            }
+            FramebufferError::RowLength { row_length, width } => write!(
+                f,
+                "rows of {row_length} pixels are shorter than the framebuffer width of {width}"
+            ),
+            FramebufferError::UnsupportedInternalFormat(format) => {
+                write!(f, "the texture can't use the internal format {format:#06x}")
+            }
+            FramebufferError::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "pixel ({x}, {y}) is outside of the {width}x{height} framebuffer"
+            ),
+            FramebufferError::InvalidSize { width, height, .. } if *width == 0 || *height == 0 => {
+                write!(f, "a {width}x{height} framebuffer has no pixels")
+            }
+            FramebufferError::InvalidSize { width, height, max } => write!(
+                f,
+                "a {width}x{height} framebuffer exceeds the maximum texture size of {max}x{max}"
+            ),
+            FramebufferError::SwapBuffers(err) => write!(f, "failed to swap buffers: {err}"),
+            #[cfg(feature = "png")]
+            FramebufferError::Image(err) => write!(f, "failed to save image: {err}"),
+            FramebufferError::SwapInterval(err) => {
+                write!(f, "failed to set the swap interval: {err}")
+            }
+            FramebufferError::ContextLost => write!(f, "the OpenGL context was lost"),
+            FramebufferError::Headless => {
+                write!(f, "the framebuffer is headless and has no window surface")
+            }
+            FramebufferError::Shader(err) => err.fmt(f),
+            FramebufferError::Program(err) => err.fmt(f),
+            FramebufferError::EventLoop(err) => write!(f, "the event loop failed: {err}"),
+            #[cfg(feature = "software")]
+            FramebufferError::Software(err) => write!(f, "software rendering failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FramebufferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FramebufferError::ContextCreation(err) => Some(err),
+            FramebufferError::Gl(err) => Some(err),
+            FramebufferError::SwapBuffers(err) => Some(err),
+            #[cfg(feature = "png")]
+            FramebufferError::Image(err) => Some(err),
+            FramebufferError::SwapInterval(err) => Some(err),
+            FramebufferError::Shader(err) => Some(err),
+            FramebufferError::Program(err) => Some(err),
+            FramebufferError::EventLoop(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl FramebufferError {
+    /// Replace errors which mean the context is gone with [`FramebufferError::ContextLost`].
+    ///
+    /// The GL state is undefined after `GL_OUT_OF_MEMORY` as well, so it counts as lost too.
+    fn detect_context_loss(self) -> Self {
+        let lost = match &self {
+            FramebufferError::Gl(err) => {
+                matches!(
+                    err.kind,
+                    GlErrorKind::ContextLost | GlErrorKind::OutOfMemory
+                )
+            }
+            FramebufferError::ContextCreation(err) | FramebufferError::SwapBuffers(err) => {
+                err.error_kind() == glutin::error::ErrorKind::ContextLost
+            }
+            _ => false,
+        };
+        if lost {
+            FramebufferError::ContextLost
+        } else {
+            self
+        }
+    }
+}
+
+/// Settings the GL objects of a framebuffer are created with, see [`Framebuffer::setup_gl`].
+struct GlSetup<'a> {
+    capabilities: &'a Capabilities,
+    fragment_shader: Option<&'a str>,
+    origin: Origin,
+    wrap_mode: WrapMode,
+    // minification and magnification filter
+    filter: [Filter; 2],
+    internal_format: u32,
+    pbo_ring: usize,
+    // already checked against the capabilities
+    persistent_mapping: bool,
+    buffer_usage: BufferUsage,
+    clear_color: [f32; 4],
+}
+
+/// Everything a framebuffer draws with, created in its context by [`Framebuffer::setup_gl`].
+struct GlObjects<Format> {
+    program: glow::Program,
+    view_location: Option<glow::UniformLocation>,
+    transform_location: Option<glow::UniformLocation>,
+    vao: glow::VertexArray,
+    texture: glow::Texture,
+    read_framebuffer: glow::Framebuffer,
+    pixel_buffers: Vec<PixelBuffer<Format>>,
+}
+
+/// State of the asynchronous readback, allocated on the first [`Framebuffer::begin_readback`].
+struct Readback<Format> {
+    // framebuffer object with the texture attached so it can be read back
+    framebuffer: glow::Framebuffer,
+    pixel_buffer: PixelBuffer<Format>,
+    fence: Option<glow::Fence>,
+    // of the transfer in flight, 0 before the first one
+    generation: u64,
+}
+
+impl<Format: PixelFormat> Readback<Format> {
+    fn new(
+        gl: &glow::Context,
+        texture: glow::Texture,
+        length: usize,
+    ) -> Result<Self, FramebufferError> {
+        Ok(Readback {
+            framebuffer: Framebuffer::<Format>::create_read_framebuffer(gl, texture)?,
+            pixel_buffer: Framebuffer::create_pixel_buffer(
+                gl,
+                length,
+                glow::PIXEL_PACK_BUFFER,
+                glow::STREAM_READ,
+            )?,
+            fence: None,
+            generation: 0,
+        })
+    }
+}
+
+impl<Format> Readback<Format> {
+    fn delete(self, gl: &glow::Context) {
+        unsafe {
+            if let Some(fence) = self.fence {
+                gl.delete_sync(fence);
+            }
+            gl.delete_framebuffer(self.framebuffer);
+        }
+        self.pixel_buffer.delete(gl);
+    }
+}
+
+/// Handle to a transfer started with [`Framebuffer::begin_readback`].
+#[must_use]
+pub struct ReadbackToken {
+    generation: u64,
+}
+
+pub struct Framebuffer<Format> {
+    // window surface handles
+    // `None` for headless framebuffers
+    surface: Option<glutin::surface::Surface<WindowSurface>>,
+    ctx_handle: glutin::context::PossiblyCurrentContext,
+    // reused by framebuffers sharing this one's display
+    gl_config: Config,
+    // the context was created with, for replacing it in `reinit`
+    context_attributes: ContextAttributes,
+    // `None` for the default shader
+    fragment_shader: Option<String>,
+    // driver messages are logged, see `FramebufferBuilder::debug`
+    debug: bool,
+
+    // size of the window surface, which the pixels are scaled to
+    surface_size: [u32; 2],
+    // the last surface size was zero, e.g. a minimized window
+    minimized: bool,
+    // as reported with `set_occluded`
+    occluded: bool,
+    scale_factor: f64,
+    scaling: ScalingMode,
+    origin: Origin,
+
+    // opengl state
+    width: usize,
+    height: usize,
+    // shared with integrations like the egui overlay
+    gl: Arc<glow::Context>,
+    // ring of pixel buffers so painting the next frame doesn't wait for the upload of the last
+    pixel_buffers: Vec<PixelBuffer<Format>>,
+    current_buffer: Cell<usize>,
+    // pixel buffers holding frames for `present_next`, oldest first
+    queued: VecDeque<usize>,
+    // allocated by the first `backbuffer_mut`
+    backbuffer: Vec<Format>,
+    orphan_buffers: bool,
+    persistent_mapping: bool,
+    // persistent buffers recreated with `GL_MAP_READ_BIT` by the first `map`
+    readable_buffers: bool,
+    buffer_usage: BufferUsage,
+    texture: glow::Texture,
+    // with the texture attached, for `map` and `read_pixels`
+    read_framebuffer: glow::Framebuffer,
+    wrap_mode: WrapMode,
+    // minification and magnification filter
+    filter: [Filter; 2],
+    vao: glow::VertexArray,
+    program: glow::Program,
+    readback: Option<Readback<Format>>,
+    // of the last readback, kept across resizes so tokens of deleted readbacks never match
+    readback_generation: u64,
+    config_info: ConfigInfo,
+    capabilities: Capabilities,
+
+    clear_color: [f32; 4],
+    // blend over the clear color instead of replacing it
+    transparent: bool,
+    // sRGB texture and `GL_FRAMEBUFFER_SRGB` during the blit
+    srgb: bool,
+    // of the texture, picked from the format, sRGB and the builder
+    internal_format: u32,
+    max_latency: Option<usize>,
+    // signaled as the GPU finishes presented frames, oldest first
+    latency_fences: VecDeque<glow::Fence>,
+
+    // pan / zoom transform of the presented image
+    view: ([f32; 2], f32),
+    view_location: Option<glow::UniformLocation>,
+    // column-major matrix applied to the quad
+    transform: [[f32; 4]; 4],
+    transform_location: Option<glow::UniformLocation>,
+    allow_overscroll: bool,
+
+    clock: FrameClock,
+    metrics: Metrics,
+    // `glFinish` after the measured phases
+    sync_metrics: bool,
+}
+
+#[derive(Default)]
+pub struct FramebufferConfigExt {
+    pub clear_color: Option<[f32; 4]>,
+    pub wrap_mode: WrapMode,
+    /// Minimum depth buffer size to request, for drawing custom 3D content over the framebuffer.
+    ///
+    /// `draw` only clears the color buffer, so `DEPTH_BUFFER_BIT` has to be cleared manually.
+    pub depth_bits: Option<u8>,
+    /// Minimum stencil buffer size to request.
+    pub stencil_bits: Option<u8>,
+    /// Let [`Framebuffer::set_view`] move the visible region past the edges of the image.
+    pub allow_overscroll: bool,
+    /// Orphan the pixel buffer before each [`Framebuffer::draw`] to avoid waiting for the GPU.
+    ///
+    /// Whether this pays off depends on the driver, the `uploads` example times both modes. For
+    /// 1920x1080 RGBA8 frames with Mesa's llvmpipe on a single core, orphaning raised the mean
+    /// [`Metrics::upload`] from 0.7 ms to 1.3 ms and lowered the throughput from about 860 to
+    /// 550 frames/s, as it allocates new storage every frame without anything to overlap with.
+    pub orphan_buffers: bool,
+    /// Keep the pixel buffers mapped across frames so drawing never has to map them again.
+    ///
+    /// Requires OpenGL 4.4 or `GL_ARB_buffer_storage`, falls back to mapping every frame
+    /// otherwise. Takes precedence over `orphan_buffers`. The buffers are write-only until the
+    /// first [`Framebuffer::map`], which recreates them readable.
+    pub persistent_mapping: bool,
+}
+
+/// Internal formats accepted by [`FramebufferBuilder::internal_format`].
+const SAMPLEABLE_FORMATS: [u32; 20] = [
+    glow::R8,
+    glow::RG8,
+    glow::RGB8,
+    glow::RGBA8,
+    glow::SRGB8,
+    glow::SRGB8_ALPHA8,
+    glow::RGB565,
+    glow::RGBA4,
+    glow::RGB5_A1,
+    glow::RGB10_A2,
+    glow::R16,
+    glow::RG16,
+    glow::RGBA16,
+    glow::R16F,
+    glow::RG16F,
+    glow::RGBA16F,
+    glow::R32F,
+    glow::RG32F,
+    glow::RGBA32F,
+    glow::R11F_G11F_B10F,
+];
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Configuration of a [`Framebuffer`] and its window, finished with
+/// [`FramebufferBuilder::build`].
+pub struct FramebufferBuilder<Format> {
+    width: usize,
+    height: usize,
+    window_size: Option<(usize, usize)>,
+    scaling: ScalingMode,
+    origin: Origin,
+    fragment_shader: Option<String>,
+    transform: [[f32; 4]; 4],
+    title: Option<String>,
+    resizable: bool,
+    decorations: bool,
+    position: Option<(i32, i32)>,
+    fullscreen: Option<winit::window::Fullscreen>,
+    window_icon: Option<winit::window::Icon>,
+    cursor_visible: bool,
+    transparent: bool,
+    srgb: bool,
+    internal_format: Option<u32>,
+    clear_color: [f32; 4],
+    swap_interval: Option<u32>,
+    single_buffered: bool,
+    max_latency: Option<usize>,
+    pbo_ring: usize,
+    gl_version: Option<(u8, u8)>,
+    core_profile: Option<bool>,
+    debug: bool,
+    wrap_mode: WrapMode,
+    min_filter: Filter,
+    mag_filter: Filter,
+    depth_bits: Option<u8>,
+    stencil_bits: Option<u8>,
+    samples: Option<u8>,
+    allow_overscroll: bool,
+    orphan_buffers: bool,
+    persistent_mapping: bool,
+    buffer_usage: BufferUsage,
+    format: PhantomData<Format>,
+}
+
+impl<Format> Default for FramebufferBuilder<Format> {
+    fn default() -> Self {
+        Self {
+            width: 640,
+            height: 480,
+            window_size: None,
+            scaling: ScalingMode::default(),
+            origin: Origin::default(),
+            fragment_shader: None,
+            transform: IDENTITY,
+            title: None,
+            resizable: true,
+            decorations: true,
+            position: None,
+            fullscreen: None,
+            window_icon: None,
+            cursor_visible: true,
+            transparent: false,
+            srgb: false,
+            internal_format: None,
+            clear_color: [0.0; 4],
+            swap_interval: None,
+            single_buffered: false,
+            max_latency: None,
+            pbo_ring: 2,
+            gl_version: None,
+            core_profile: None,
+            debug: false,
+            wrap_mode: WrapMode::default(),
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Linear,
+            depth_bits: None,
+            stencil_bits: None,
+            samples: None,
+            allow_overscroll: false,
+            orphan_buffers: false,
+            persistent_mapping: false,
+            buffer_usage: BufferUsage::default(),
+            format: PhantomData,
+        }
+    }
+}
+
+impl<Format> FramebufferBuilder<Format> {
+    /// Builder for a 640x480 framebuffer with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of the framebuffer in pixels, and initially of the window unless
+    /// [`FramebufferBuilder::window_size`] is set.
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Initial size of the window in physical pixels, if it should differ from the framebuffer.
+    pub fn window_size(mut self, width: usize, height: usize) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// How the framebuffer is fit into a window of a different size, stretched by default.
+    pub fn scaling(mut self, scaling: ScalingMode) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// Where the first row of pixels is shown, at the top by default.
+    ///
+    /// Everything indexing pixels, like [`PixelView`] rows and dirty rects, counts rows from
+    /// this origin. [`Framebuffer::read_pixels`] returns the rows in the same order, which for
+    /// [`Origin::BottomLeft`] is also the order of OpenGL's `glReadPixels`.
+    pub fn origin(mut self, origin: Origin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Replace the fragment shader, e.g. for gamma correction or tone mapping.
+    ///
+    /// The shader receives the texture coordinate as `in vec2 TexCoord` and the uploaded pixels
+    /// as `uniform sampler2D texture1` bound to texture unit 0, and writes the first output, e.g.
+    /// `out vec4 FragColor`. It has to be GLSL `#version 330 core` or compatible. Compilation
+    /// errors are reported by [`FramebufferBuilder::build`]. Additional uniforms can be set
+    /// through [`Framebuffer::gl`] on [`Framebuffer::program`].
+    pub fn fragment_shader(mut self, source: impl Into<String>) -> Self {
+        self.fragment_shader = Some(source.into());
+        self
+    }
+
+    /// Matrix applied to the corners of the quad the pixels are drawn on, e.g. to show them in
+    /// a smaller rectangle as a picture-in-picture.
+    ///
+    /// The matrix is column-major like in GLSL, `transform[column][row]`, and maps the quad's
+    /// corners `(±1, ±1)` in the clip space of the region picked by the [`ScalingMode`], which
+    /// also clips the result. The default identity covers the whole region.
+    ///
+    /// ```
+    /// // the top-right quarter
+    /// let transform = [
+    ///     [0.5, 0.0, 0.0, 0.0],
+    ///     [0.0, 0.5, 0.0, 0.0],
+    ///     [0.0, 0.0, 1.0, 0.0],
+    ///     [0.5, 0.5, 0.0, 1.0],
+    /// ];
+    /// let builder = cpf::FramebufferBuilder::<[u8; 4]>::new().transform(transform);
+    /// ```
+    pub fn transform(mut self, transform: [[f32; 4]; 4]) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Whether the user can resize the window, `true` by default.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Whether the window has a title bar and borders, `true` by default.
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Initial position of the window's top-left corner on the desktop, in physical pixels.
+    ///
+    /// Left to the window manager by default. Some platforms, e.g. Wayland, ignore it.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Open the window in fullscreen, e.g. `Fullscreen::Borderless(None)` to cover the current
+    /// monitor, see [`Framebuffer::set_fullscreen`] to switch later.
+    ///
+    /// The framebuffer keeps its [size](FramebufferBuilder::size) and is scaled to the screen,
+    /// resize it on `WindowEvent::Resized` for one pixel per screen pixel.
+    pub fn fullscreen(mut self, fullscreen: winit::window::Fullscreen) -> Self {
+        self.fullscreen = Some(fullscreen);
+        self
+    }
+
+    /// Icon of the window, e.g. in the title bar or the taskbar.
+    ///
+    /// Some platforms only take the icon when the window is created. macOS has no window
+    /// icons and Wayland takes them from the desktop entry instead.
+    pub fn window_icon(mut self, icon: winit::window::Icon) -> Self {
+        self.window_icon = Some(icon);
+        self
+    }
+
+    /// Whether the cursor is shown over the window, `true` by default.
+    pub fn cursor_visible(mut self, visible: bool) -> Self {
+        self.cursor_visible = visible;
+        self
+    }
+
+    /// Let the desktop show through pixels with an alpha below 255.
+    ///
+    /// The framebuffer is alpha blended over the clear color, which should then be transparent
+    /// too, e.g. the default of `[0.0; 4]`. Painters have to write an alpha of 255 for opaque
+    /// pixels, those writing 0 become invisible, which debug builds warn about on the first
+    /// frame. Whether the window can actually be transparent depends on the platform and
+    /// compositor.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Present through an sRGB window surface with `GL_FRAMEBUFFER_SRGB`.
+    ///
+    /// 8 bit pixels are always interpreted as sRGB encoded, the usual encoding of images and
+    /// colors picked on screen. By default they are shown exactly as written, but filtering and
+    /// blending then mix encoded values, which e.g. darkens edges of linearly filtered images.
+    /// With sRGB they are decoded to linear values for filtering and blending and encoded again
+    /// for display. Floating point pixels are linear in that case, and shown as written
+    /// otherwise. Grayscale `u8` pixels have no sRGB texture format and are treated as linear.
+    ///
+    /// Falls back to the default with a warning if the picked config is not sRGB capable.
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Sized internal format of the texture, instead of the pixel format's
+    /// [`PixelFormat::INTERNAL_FORMAT`] or, with [`FramebufferBuilder::srgb`],
+    /// [`PixelFormat::SRGB_INTERNAL_FORMAT`].
+    ///
+    /// E.g. `glow::RGBA16F` keeps the precision of float pixels in half the memory of the
+    /// default `glow::RGBA32F`. The texture is sampled with filtering and read back through a
+    /// framebuffer object, so only filterable, color-renderable formats are accepted, other
+    /// formats fail the build with [`FramebufferError::UnsupportedInternalFormat`]:
+    ///
+    /// `R8`, `RG8`, `RGB8`, `RGBA8`, `SRGB8`, `SRGB8_ALPHA8`, `RGB565`, `RGBA4`, `RGB5_A1`,
+    /// `RGB10_A2`, `R16`, `RG16`, `RGBA16`, `R16F`, `RG16F`, `RGBA16F`, `R32F`, `RG32F`,
+    /// `RGBA32F` and `R11F_G11F_B10F`.
+    pub fn internal_format(mut self, internal_format: u32) -> Self {
+        self.internal_format = Some(internal_format);
+        self
+    }
+
+    pub fn clear_color(mut self, rgba: [f32; 4]) -> Self {
+        self.clear_color = rgba;
+        self
+    }
+
+    /// Synchronize presentation with the display refresh, the platform default is kept if unset.
+    ///
+    /// A request rejected by the platform is logged rather than failing the build.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.swap_interval = Some(vsync as u32);
+        self
+    }
+
+    /// Wait for `interval` display refreshes between frames, 0 presents immediately.
+    ///
+    /// Generalizes [`FramebufferBuilder::vsync`], which sets an interval of 1 or 0. Intervals
+    /// above 1 are mostly honored by EGL and GLX, other platforms may clamp them to 1.
+    pub fn swap_interval(mut self, interval: u32) -> Self {
+        self.swap_interval = Some(interval);
+        self
+    }
+
+    /// Draw straight to the front buffer instead of swapping a back buffer in.
+    ///
+    /// Saves a buffer and the latency of the swap at the risk of tearing. Honored by EGL, GLX,
+    /// WGL and CGL if the driver offers single buffered configs, and silently ignored otherwise.
+    /// The number of back buffers beyond that, e.g. triple buffering, is up to the driver.
+    pub fn single_buffered(mut self, single_buffered: bool) -> Self {
+        self.single_buffered = single_buffered;
+        self
+    }
+
+    /// Let the CPU run at most `frames` frames ahead of the GPU.
+    ///
+    /// Drivers commonly queue a few frames, which keeps the GPU busy but delays the response to
+    /// input by as many frames. With a limit, [`Framebuffer::swap_buffers`] waits for the GPU to
+    /// finish the frame from `frames` swaps ago, 0 waits for the frame just presented. Works on
+    /// all platforms as it only relies on fences.
+    pub fn max_latency(mut self, frames: usize) -> Self {
+        self.max_latency = Some(frames);
+        self
+    }
+
+    /// Number of pixel buffers the frames are painted into in turn, 2 by default.
+    ///
+    /// While the GPU uploads a frame from one buffer the next is painted into another, so more
+    /// buffers let the CPU run further ahead of slow uploads, and let
+    /// [`Framebuffer::enqueue_frame`] queue more frames. One buffer makes every frame wait for
+    /// the upload of the previous one. Each buffer holds a whole frame, i.e.
+    /// `width * height * size_of::<Format>()` bytes of GPU memory.
+    ///
+    /// The `uploads` example measures the throughput of 1920x1080 RGBA8 frames for rings of 1 to
+    /// 4 buffers, timing 200 painted and uploaded frames until the GPU is done with them. With
+    /// Mesa's llvmpipe on a single core it reaches 870 to 930 frames/s, 0.7 ms of upload per
+    /// frame, for every ring size, as the software renderer copies during the upload call
+    /// anyway. Run it on the target hardware to pick a size.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is 0.
+    pub fn pbo_ring(mut self, count: usize) -> Self {
+        assert!(count >= 1, "at least one pixel buffer is needed");
+        self.pbo_ring = count;
+        self
+    }
+
+    /// OpenGL version to request for the context.
+    ///
+    /// The built-in shaders need at least OpenGL 3.3 (`#version 330`), uploads and sampler
+    /// setup use direct state access from OpenGL 4.5. Older contexts need
+    /// `GL_ARB_direct_state_access`, building fails with [`FramebufferError::UnsupportedVersion`]
+    /// without it. If the requested context can't be created the platform default is used
+    /// instead.
+    pub fn gl_version(mut self, major: u8, minor: u8) -> Self {
+        self.gl_version = Some((major, minor));
+        self
+    }
+
+    /// Request a core (`true`) or compatibility (`false`) profile context.
+    ///
+    /// Falls back to the platform default like [`FramebufferBuilder::gl_version`].
+    pub fn core_profile(mut self, core: bool) -> Self {
+        self.core_profile = Some(core);
+        self
+    }
+
+    /// Request a debug context and log the driver's messages about e.g. invalid calls, shader
+    /// problems and slow paths, off by default.
+    ///
+    /// The messages go through the `log` crate, high severity as errors down to notifications
+    /// as debug output. Debug contexts can be considerably slower, so this is meant for
+    /// development only. Drivers are free to ignore the request or to report nothing, and
+    /// without OpenGL 4.3 or `GL_KHR_debug` there are no messages at all.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    /// Filter used when the framebuffer is shown smaller than its size, nearest by default.
+    pub fn min_filter(mut self, filter: Filter) -> Self {
+        self.min_filter = filter;
+        self
+    }
+
+    /// Filter used when the framebuffer is shown larger than its size, linear by default.
+    ///
+    /// Use [`Filter::Nearest`] for crisp upscaling of pixel art.
+    pub fn mag_filter(mut self, filter: Filter) -> Self {
+        self.mag_filter = filter;
+        self
+    }
+
+    /// See [`FramebufferConfigExt::depth_bits`].
+    pub fn depth_bits(mut self, bits: u8) -> Self {
+        self.depth_bits = Some(bits);
+        self
+    }
+
+    /// See [`FramebufferConfigExt::stencil_bits`].
+    pub fn stencil_bits(mut self, bits: u8) -> Self {
+        self.stencil_bits = Some(bits);
+        self
+    }
+
+    /// Request a multisampled window with `samples` samples per pixel, a power of two.
+    ///
+    /// The framebuffer itself is a single textured quad which gains nothing from multisampling,
+    /// only custom geometry drawn over it, e.g. an overlay, is antialiased. Building fails with
+    /// [`FramebufferError::ConfigSelection`] if no config supports the requested count. By
+    /// default the config with the fewest samples is picked.
+    pub fn samples(mut self, samples: u8) -> Self {
+        self.samples = Some(samples);
+        self
+    }
+
+    /// See [`FramebufferConfigExt::allow_overscroll`].
+    pub fn allow_overscroll(mut self, allow: bool) -> Self {
+        self.allow_overscroll = allow;
+        self
+    }
+
+    /// See [`FramebufferConfigExt::orphan_buffers`].
+    pub fn orphan_buffers(mut self, orphan: bool) -> Self {
+        self.orphan_buffers = orphan;
+        self
+    }
+
+    /// See [`FramebufferConfigExt::persistent_mapping`].
+    pub fn persistent_mapping(mut self, persistent: bool) -> Self {
+        self.persistent_mapping = persistent;
+        self
+    }
+
+    /// How often the pixels are expected to change, passed to the driver when allocating the
+    /// pixel buffers. [`BufferUsage::Stream`] by default.
+    ///
+    /// Ignored with [`FramebufferConfigExt::persistent_mapping`], whose storage is immutable.
+    pub fn buffer_usage(mut self, usage: BufferUsage) -> Self {
+        self.buffer_usage = usage;
+        self
+    }
+
+    fn window_attributes(&self) -> winit::window::WindowAttributes {
+        let (width, height) = self.window_size.unwrap_or((self.width, self.height));
+        let mut window_attributes = Window::default_attributes()
+            .with_inner_size(PhysicalSize {
+                width: width as u32,
+                height: height as u32,
+            })
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations)
+            .with_fullscreen(self.fullscreen.clone())
+            .with_window_icon(self.window_icon.clone())
+            .with_transparent(self.transparent);
+        if let Some(title) = &self.title {
+            window_attributes = window_attributes.with_title(title);
+        }
+        if let Some((x, y)) = self.position {
+            window_attributes = window_attributes.with_position(PhysicalPosition { x, y });
+        }
+        window_attributes
+    }
+
+    /// Create a context with the requested version and profile, falling back to the defaults.
+    ///
+    /// Also returns the attributes which succeeded, without the sharing, to create a replacement
+    /// context with later.
+    unsafe fn create_context<D: GlDisplay>(
+        &self,
+        display: &D,
+        config: &D::Config,
+        raw_window_handle: Option<RawWindowHandle>,
+        share: Option<&glutin::context::PossiblyCurrentContext>,
+    ) -> Result<(D::NotCurrentContext, ContextAttributes), FramebufferError> {
+        let shared = |attributes: &ContextAttributesBuilder| match share {
+            Some(share) => attributes.clone().with_sharing(share),
+            None => attributes.clone(),
+        };
+        let mut context_attributes = ContextAttributesBuilder::new().with_debug(self.debug);
+        let fallback_attributes = ContextAttributesBuilder::new().with_debug(self.debug);
+        if let Some((major, minor)) = self.gl_version {
+            context_attributes = context_attributes
+                .with_context_api(ContextApi::OpenGl(Some(Version::new(major, minor))));
+        }
+        if let Some(core) = self.core_profile {
+            context_attributes = context_attributes.with_profile(if core {
+                GlProfile::Core
+            } else {
+                GlProfile::Compatibility
+            });
+        }
+        display
+            .create_context(
+                config,
+                &shared(&context_attributes).build(raw_window_handle),
+            )
+            .map(|ctx| (ctx, context_attributes.build(raw_window_handle)))
+            .or_else(|err| {
+                if self.gl_version.is_none() && self.core_profile.is_none() {
+                    return Err(err);
+                }
+                // fall back to whatever the platform provides by default
+                log!(
+                    warn,
+                    "failed to create the requested context, using defaults: {err}"
+                );
+                display
+                    .create_context(
+                        config,
+                        &shared(&fallback_attributes).build(raw_window_handle),
+                    )
+                    .map(|ctx| (ctx, fallback_attributes.build(raw_window_handle)))
+            })
+            .map_err(FramebufferError::ContextCreation)
+    }
+
+    fn ext(self, ext: FramebufferConfigExt) -> Self {
+        Self {
+            clear_color: ext.clear_color.unwrap_or_default(),
+            wrap_mode: ext.wrap_mode,
+            depth_bits: ext.depth_bits,
+            stencil_bits: ext.stencil_bits,
+            allow_overscroll: ext.allow_overscroll,
+            orphan_buffers: ext.orphan_buffers,
+            persistent_mapping: ext.persistent_mapping,
+            ..self
+        }
+    }
+}
+
+impl<Format: PixelFormat> FramebufferBuilder<Format> {
+    /// Create the window and the framebuffer presented in it.
+    pub fn build(
+        self,
+        event_loop: &ActiveEventLoop,
+    ) -> Result<(winit::window::Window, Framebuffer<Format>), FramebufferError> {
+        Framebuffer::from_builder(event_loop, self)
+    }
+
+    /// Create another window and framebuffer on the display of `share`.
+    ///
+    /// The new context shares objects like textures and programs with the one of `share`, and
+    /// uses the same OpenGL config, so the depth and stencil options are ignored. Building makes
+    /// the new context current, drawing switches back as needed, see
+    /// [`Framebuffer::make_current`].
+    pub fn build_shared<Other>(
+        self,
+        event_loop: &ActiveEventLoop,
+        share: &Framebuffer<Other>,
+    ) -> Result<(winit::window::Window, Framebuffer<Format>), FramebufferError> {
+        Framebuffer::shared_from_builder(event_loop, self, share)
+    }
+
+    /// Create a framebuffer without a window, e.g. to render images on a machine without a
+    /// display.
+    ///
+    /// The pixels are uploaded with [`Framebuffer::update`] and read back with
+    /// [`Framebuffer::read_pixels`] or [`Framebuffer::save_png`], there is nothing to
+    /// [`Framebuffer::present`] to. Needs EGL with `EGL_EXT_device_enumeration` and surfaceless
+    /// contexts, so it is not available on macOS.
+    #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+    pub fn build_headless(self) -> Result<Framebuffer<Format>, FramebufferError> {
+        Framebuffer::headless_from_builder(self)
+    }
+
+    /// Set up the framebuffer on a context and surface created by the application, e.g. one
+    /// that also draws other OpenGL content.
+    ///
+    /// The framebuffer takes ownership of both. `ctx` has to be current on this thread with
+    /// `surface`, and `gl` loaded for it. Options that select the config or create the window,
+    /// like the depth bits or the title, have no effect as that already happened.
+    pub fn build_with_context(
+        self,
+        gl: glow::Context,
+        surface: glutin::surface::Surface<WindowSurface>,
+        ctx: glutin::context::PossiblyCurrentContext,
+    ) -> Result<Framebuffer<Format>, FramebufferError> {
+        debug_assert!(ctx.is_current(), "the context must be current");
+        let surface_size = [
+            surface.width().unwrap_or(self.width as u32),
+            surface.height().unwrap_or(self.height as u32),
+        ];
+        let gl_config = ctx.config();
+        Framebuffer::from_context(self, Some(surface), surface_size, ctx, gl_config, gl)
+    }
+
+    /// Internal format of the texture, the validated override or the pixel format's default.
+    fn texture_internal_format(&self, srgb: bool) -> Result<u32, FramebufferError> {
+        match self.internal_format {
+            Some(format) if SAMPLEABLE_FORMATS.contains(&format) => Ok(format),
+            Some(format) => Err(FramebufferError::UnsupportedInternalFormat(format)),
+            None => Ok(Format::SRGB_INTERNAL_FORMAT
+                .filter(|_| srgb)
+                .unwrap_or(Format::INTERNAL_FORMAT)),
+        }
+    }
+}
+
+/// How texels are interpolated when the texture is scaled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Use the closest texel (`GL_NEAREST`).
+    Nearest,
+    /// Interpolate between neighbouring texels (`GL_LINEAR`).
+    Linear,
+}
+
+impl Filter {
+    fn gl_enum(self) -> u32 {
+        match self {
+            Filter::Nearest => glow::NEAREST,
+            Filter::Linear => glow::LINEAR,
+        }
+    }
+}
+
+/// Properties of the OpenGL config picked at initialization.
+///
+/// Tells which of the requested options the platform could provide, e.g. when a window stays
+/// opaque or aliased. Logged at debug level once the framebuffer is created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConfigInfo {
+    /// Bits of the red, green and blue channels, zero for luminance configs.
+    pub color_bits: [u8; 3],
+    pub alpha_bits: u8,
+    pub depth_bits: u8,
+    pub stencil_bits: u8,
+    /// Multisampling samples per pixel, 0 without multisampling.
+    pub samples: u8,
+    /// Whether the config can show the desktop through transparent pixels, see
+    /// [`FramebufferBuilder::transparent`].
+    pub transparency: bool,
+    /// Whether the config can encode sRGB, see [`FramebufferBuilder::srgb`].
+    pub srgb: bool,
+    pub hardware_accelerated: bool,
+}
+
+impl ConfigInfo {
+    fn from_config(config: &Config) -> Self {
+        let color_bits = match config.color_buffer_type() {
+            Some(ColorBufferType::Rgb {
+                r_size,
+                g_size,
+                b_size,
+            }) => [r_size, g_size, b_size],
+            _ => [0; 3],
+        };
+        ConfigInfo {
+            color_bits,
+            alpha_bits: config.alpha_size(),
+            depth_bits: config.depth_size(),
+            stencil_bits: config.stencil_size(),
+            samples: config.num_samples(),
+            transparency: config.supports_transparency().unwrap_or(false),
+            srgb: config.srgb_capable(),
+            hardware_accelerated: config.hardware_accelerated(),
+        }
+    }
+
+    /// Rank of a config for `builder`, compared lexicographically with higher being better:
+    ///
+    /// 1. transparency support, only when a transparent window was requested
+    /// 2. sRGB support, only when sRGB presentation was requested
+    /// 3. multisampling samples closest to the requested count, the fewest if none was requested
+    /// 4. total bits of the red, green and blue channels
+    /// 5. bits of the alpha channel
+    fn score<Format>(
+        &self,
+        builder: &FramebufferBuilder<Format>,
+    ) -> (bool, bool, Reverse<u8>, u16, u8) {
+        (
+            builder.transparent && self.transparency,
+            builder.srgb && self.srgb,
+            Reverse(self.samples.abs_diff(builder.samples.unwrap_or(0))),
+            self.color_bits.iter().map(|&bits| bits as u16).sum(),
+            self.alpha_bits,
+        )
+    }
+}
+
+/// Item of `items` with the highest `score`, the first one on ties, so the result doesn't
+/// depend on the order of the others.
+fn best_by_score<T, S: Ord>(
+    items: impl IntoIterator<Item = T>,
+    score: impl Fn(&T) -> S,
+) -> Option<T> {
+    items
+        .into_iter()
+        .map(|item| (score(&item), item))
+        .reduce(|best, next| if next.0 > best.0 { next } else { best })
+        .map(|(_, item)| item)
+}
+
+/// What the OpenGL driver supports, queried once the context is created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// OpenGL version as `(major, minor)`.
+    pub version: (u32, u32),
+    /// Whether the context is OpenGL ES rather than desktop OpenGL.
+    pub embedded: bool,
+    /// Driver specific part of the version string, e.g. `"Mesa 24.0.5"`.
+    pub vendor_info: String,
+    /// Name of the GPU or software renderer.
+    pub renderer: String,
+    /// Largest supported width and height of a texture, and hence of the framebuffer.
+    pub max_texture_size: u32,
+    /// Immutable buffer storage, OpenGL 4.4 or `GL_ARB_buffer_storage`.
+    pub supports_buffer_storage: bool,
+    /// Persistently mapped buffers, see [`FramebufferConfigExt::persistent_mapping`]. Comes
+    /// with buffer storage.
+    pub supports_persistent_map: bool,
+    /// Direct state access, OpenGL 4.5 or `GL_ARB_direct_state_access`.
+    pub supports_direct_state_access: bool,
+    /// Debug message callbacks, OpenGL 4.3 or `GL_KHR_debug`.
+    pub supports_debug_output: bool,
+}
+
+impl Capabilities {
+    fn query(gl: &glow::Context) -> Self {
+        let version = gl.version();
+        let extensions = gl.supported_extensions();
+        let desktop_at_least =
+            |major, minor| !version.is_embedded && (version.major, version.minor) >= (major, minor);
+        let supports_buffer_storage =
+            desktop_at_least(4, 4) || extensions.contains("GL_ARB_buffer_storage");
+        Self {
+            version: (version.major, version.minor),
+            embedded: version.is_embedded,
+            vendor_info: version.vendor_info.clone(),
+            renderer: unsafe { gl.get_parameter_string(glow::RENDERER) },
+            max_texture_size: unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) } as u32,
+            supports_buffer_storage,
+            supports_persistent_map: supports_buffer_storage,
+            supports_direct_state_access: desktop_at_least(4, 5)
+                || extensions.contains("GL_ARB_direct_state_access"),
+            supports_debug_output: desktop_at_least(4, 3) || extensions.contains("GL_KHR_debug"),
+        }
+    }
+
+    /// Check that a framebuffer of the size can be created.
+    fn check_size(&self, width: usize, height: usize) -> Result<(), FramebufferError> {
+        let max = self.max_texture_size as usize;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(FramebufferError::InvalidSize {
+                width,
+                height,
+                max: self.max_texture_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// How the framebuffer is fit into a window of a different size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// Cover the whole window, distorting the aspect ratio.
+    #[default]
+    Stretch,
+    /// Scale by the largest whole number that fits and center, for pixel perfect output.
+    ///
+    /// Windows smaller than the framebuffer fall back to [`ScalingMode::FitContain`].
+    IntegerCenter,
+    /// Scale as large as fits while keeping the aspect ratio and center.
+    ///
+    /// The window is letterboxed, the bars on either side show the clear color.
+    FitContain,
+}
+
+/// Which corner of the window the first row of pixels is shown at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Origin {
+    /// Rows go from the top of the window down, like in most image formats.
+    #[default]
+    TopLeft,
+    /// Rows go from the bottom of the window up, like OpenGL textures and framebuffers.
+    BottomLeft,
+}
+
+/// Usage hint for the pixel buffers, which drivers use to decide where to place them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BufferUsage {
+    /// Written every frame and uploaded once (`GL_STREAM_DRAW`).
+    #[default]
+    Stream,
+    /// Written repeatedly, every now and then (`GL_DYNAMIC_DRAW`).
+    Dynamic,
+    /// Written once or rarely, e.g. for a splash screen (`GL_STATIC_DRAW`).
+    Static,
+}
+
+impl BufferUsage {
+    fn gl_enum(self) -> u32 {
+        match self {
+            BufferUsage::Stream => glow::STREAM_DRAW,
+            BufferUsage::Dynamic => glow::DYNAMIC_DRAW,
+            BufferUsage::Static => glow::STATIC_DRAW,
+        }
+    }
+}
+
+/// How the texture is sampled outside of the `[0, 1]` coordinate range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Repeat the edge pixels (`GL_CLAMP_TO_EDGE`).
+    #[default]
+    Clamp,
+    /// Tile the texture (`GL_REPEAT`).
+    Repeat,
+    /// Tile the texture, mirroring every other tile (`GL_MIRRORED_REPEAT`).
+    MirroredRepeat,
+}
+
+impl WrapMode {
+    fn gl_enum(self) -> u32 {
+        match self {
+            WrapMode::Clamp => glow::CLAMP_TO_EDGE,
+            WrapMode::Repeat => glow::REPEAT,
+            WrapMode::MirroredRepeat => glow::MIRRORED_REPEAT,
+        }
+    }
+}
+
+impl<Format: PixelFormat> Framebuffer<Format> {
+    /// Pick the config with the highest [`ConfigInfo::score`], the first one on ties.
+    fn gl_config_picker(
+        configs: Box<dyn Iterator<Item = Config> + '_>,
+        builder: &FramebufferBuilder<Format>,
+    ) -> Config {
+        best_by_score(configs, |config| {
+            ConfigInfo::from_config(config).score(builder)
+        })
+        .unwrap()
+    }
+
+    /// Properties of the OpenGL config the framebuffer was created with.
+    pub fn config_info(&self) -> ConfigInfo {
+        self.config_info
+    }
+
+    /// What the OpenGL driver supports.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// The OpenGL context, for issuing custom draw calls.
+    ///
+    /// [`Framebuffer::draw`] rebinds every object it uses, but global state changed through the
+    /// context, such as the clear color, blending or depth testing, is left as is.
+    pub fn gl(&self) -> &glow::Context {
+        &self.gl
+    }
+
+    /// Shader program drawing the texture to the window.
+    ///
+    /// Uniform values are part of the program's state, so values set after
+    /// `gl.use_program(Some(framebuffer.program()))` stay in effect for every following frame.
+    pub fn program(&self) -> glow::Program {
+        self.program
+    }
+
+    /// Texture holding the last uploaded frame, e.g. to sample it from custom shaders.
+    ///
+    /// Valid in the framebuffer's context, or contexts sharing with it, until the framebuffer is
+    /// dropped, [`Framebuffer::resize`]d or [`Framebuffer::reinit`]ialized, each of which
+    /// replaces it. Its size is [`Framebuffer::dimensions`] and its storage is immutable.
+    /// Contents written to it directly are overwritten by the next upload.
+    pub fn texture(&self) -> glow::Texture {
+        self.texture
+    }
+
+    /// Pixel buffer the next frame is painted into, e.g. to fill it from other GL code and
+    /// upload it with [`Framebuffer::upload_to_texture`].
+    ///
+    /// Its contents are overwritten by the next [`Framebuffer::update`]. The buffer holds
+    /// `width * height` pixels of `Format`. It changes after every upload as the framebuffer
+    /// moves on through its [`FramebufferBuilder::pbo_ring`], and is replaced like
+    /// [`Framebuffer::texture`]. With [`FramebufferConfigExt::persistent_mapping`] the buffer is
+    /// mapped for its whole lifetime and must not be mapped again.
+    pub fn pixel_buffer(&self) -> glow::Buffer {
+        self.pixel_buffers[self.current_buffer.get()].raw_buffer
+    }
+
+    /// Turn synchronization of presentation with the display refresh on or off.
+    pub fn set_vsync(&mut self, on: bool) -> Result<(), FramebufferError> {
+        self.set_swap_interval(on as u32)
+    }
+
+    /// Wait for `interval` display refreshes between frames, see
+    /// [`FramebufferBuilder::swap_interval`].
+    pub fn set_swap_interval(&mut self, interval: u32) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        let interval = match NonZeroU32::new(interval) {
+            Some(interval) => SwapInterval::Wait(interval),
+            None => SwapInterval::DontWait,
+        };
+        self.surface
+            .as_ref()
+            .ok_or(FramebufferError::Headless)?
+            .set_swap_interval(&self.ctx_handle, interval)
+            .map_err(FramebufferError::SwapInterval)
+    }
+
+    /// Change the color of the area not covered by the framebuffer, from the next frame on.
+    ///
+    /// The framebuffer's context must be current, see [`Framebuffer::make_current`].
+    pub fn set_clear_color(&mut self, rgba: [f32; 4]) {
+        self.debug_assert_current();
+        let [r, g, b, a] = rgba;
+        unsafe {
+            self.gl.clear_color(r, g, b, a);
+            check!(self.gl);
+        }
+        self.clear_color = rgba;
+    }
+
+    pub fn clear_color(&self) -> [f32; 4] {
+        self.clear_color
+    }
+
+    /// Change the minification and magnification filters of the texture.
+    ///
+    /// The framebuffer's context must be current, see [`Framebuffer::make_current`].
+    pub fn set_filter(&mut self, min: Filter, mag: Filter) {
+        self.debug_assert_current();
+        let gl = &self.gl;
+        unsafe {
+            gl.texture_parameter_i32(self.texture, glow::TEXTURE_MIN_FILTER, min.gl_enum() as i32);
+            check!(gl);
+            gl.texture_parameter_i32(self.texture, glow::TEXTURE_MAG_FILTER, mag.gl_enum() as i32);
+            check!(gl);
+        }
+        self.filter = [min, mag];
+    }
+
+    /// Pan and zoom the presented image without resampling it on the CPU.
+    ///
+    /// `offset` moves the center of the visible region in fractions of the framebuffer size,
+    /// positive values pan right and down. `zoom` greater than one magnifies the image. Unless
+    /// overscroll was allowed at initialization the offset is clamped to keep the view within
+    /// the image.
+    pub fn set_view(&mut self, offset: [f32; 2], zoom: f32) {
+        assert!(zoom > 0.0, "zoom must be positive");
+
+        let offset = if self.allow_overscroll {
+            offset
+        } else {
+            let limit = (0.5 - 0.5 / zoom).max(0.0);
+            offset.map(|axis| axis.clamp(-limit, limit))
+        };
+        self.view = (offset, zoom);
+    }
+
+    /// Current pan offset and zoom, as set by [`Framebuffer::set_view`].
+    pub fn view(&self) -> ([f32; 2], f32) {
+        self.view
+    }
+
+    /// Change the matrix applied to the quad from the next frame on, see
+    /// [`FramebufferBuilder::transform`].
+    pub fn set_transform(&mut self, transform: [[f32; 4]; 4]) {
+        self.transform = transform;
+    }
+
+    /// Matrix currently applied to the quad, the identity unless set.
+    pub fn transform(&self) -> [[f32; 4]; 4] {
+        self.transform
+    }
+
+    /// Width and height of the framebuffer in pixels.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Size of the window surface in physical pixels, which the framebuffer is scaled to.
+    ///
+    /// On HiDPI displays this differs from the window's logical size, e.g. a window of 640x640
+    /// logical pixels has a 1280x1280 surface at a scale factor of 2. For headless framebuffers
+    /// this is the framebuffer size.
+    pub fn surface_size(&self) -> PhysicalSize<u32> {
+        let [width, height] = self.surface_size;
+        PhysicalSize { width, height }
+    }
+
+    /// Ratio of physical to logical pixels of the window, as reported by winit at creation and
+    /// updated by [`Framebuffer::handle_scale_change`].
+    ///
+    /// 1.0 for headless framebuffers and those built with
+    /// [`FramebufferBuilder::build_with_context`], which have no window to ask.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Sized internal format of the texture, see [`FramebufferBuilder::internal_format`].
+    pub fn internal_format(&self) -> u32 {
+        self.internal_format
+    }
+
+    /// Which corner the first row of pixels is shown at, see [`FramebufferBuilder::origin`].
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    #[allow(unused)]
+    pub fn init(
+        event_loop: &ActiveEventLoop,
+        width: usize,
+        height: usize,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        Self::init_with_ext(event_loop, width, height, None)
+    }
+
+    pub fn init_with_ext(
+        event_loop: &ActiveEventLoop,
+        width: usize,
+        height: usize,
+        ext: Option<FramebufferConfigExt>,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        FramebufferBuilder::new()
+            .size(width, height)
+            .ext(ext.unwrap_or_default())
+            .build(event_loop)
+    }
+
+    fn from_builder(
+        event_loop: &ActiveEventLoop,
+        builder: FramebufferBuilder<Format>,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        let (window, gl_config) = {
+            let mut template = ConfigTemplateBuilder::new()
+                .with_alpha_size(8)
+                .with_transparency(builder.transparent)
+                .with_single_buffering(builder.single_buffered);
+            if let Some(depth_bits) = builder.depth_bits {
+                template = template.with_depth_size(depth_bits);
+            }
+            if let Some(stencil_bits) = builder.stencil_bits {
+                template = template.with_stencil_size(stencil_bits);
+            }
+            if let Some(samples) = builder.samples {
+                if !samples.is_power_of_two() {
+                    return Err(FramebufferError::ConfigSelection(format!(
+                        "{samples} multisampling samples is not a power of two"
+                    )));
+                }
+                template = template.with_multisampling(samples);
+            }
+
+            let display_builder =
+                DisplayBuilder::new().with_window_attributes(Some(builder.window_attributes()));
+
+            let (window, gl_config) = display_builder
+                .build(event_loop, template, |configs| {
+                    Self::gl_config_picker(configs, &builder)
+                })
+                .map_err(|err| FramebufferError::ConfigSelection(err.to_string()))?;
+
+            let window = window.ok_or_else(|| {
+                FramebufferError::ConfigSelection("no window was created".to_owned())
+            })?;
+            (window, gl_config)
+        };
+
+        Self::from_window(builder, window, gl_config, None)
+    }
+
+    /// Framebuffer in a new window sharing the display and config of `share`, see
+    /// [`FramebufferBuilder::build_shared`].
+    pub fn init_shared<Other>(
+        share: &Framebuffer<Other>,
+        event_loop: &ActiveEventLoop,
+        width: usize,
+        height: usize,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        FramebufferBuilder::new()
+            .size(width, height)
+            .build_shared(event_loop, share)
+    }
+
+    fn shared_from_builder<Other>(
+        event_loop: &ActiveEventLoop,
+        builder: FramebufferBuilder<Format>,
+        share: &Framebuffer<Other>,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        if share.is_headless() {
+            return Err(FramebufferError::Headless);
+        }
+        let window = glutin_winit::finalize_window(
+            event_loop,
+            builder.window_attributes(),
+            &share.gl_config,
+        )
+        .map_err(|err| FramebufferError::SurfaceBuild(err.to_string()))?;
+
+        Self::from_window(
+            builder,
+            window,
+            share.gl_config.clone(),
+            Some(&share.ctx_handle),
+        )
+    }
+
+    /// Create the surface and context for `window` and make them current.
+    fn from_window(
+        builder: FramebufferBuilder<Format>,
+        window: winit::window::Window,
+        gl_config: Config,
+        share: Option<&glutin::context::PossiblyCurrentContext>,
+    ) -> Result<(winit::window::Window, Self), FramebufferError> {
+        let (surface, ctx_handle, gl, context_attributes) = {
+            let display = gl_config.display();
+
+            let surface = {
+                let attrs = window
+                    .build_surface_attributes(
+                        SurfaceAttributesBuilder::<WindowSurface>::new()
+                            .with_srgb(builder.srgb.then_some(true))
+                            .with_single_buffer(builder.single_buffered),
+                    )
+                    .map_err(|err| FramebufferError::SurfaceBuild(err.to_string()))?;
+                unsafe { display.create_window_surface(&gl_config, &attrs) }
+                    .map_err(|err| FramebufferError::SurfaceBuild(err.to_string()))?
+            };
+
+            let (ctx_handle, context_attributes) = {
+                let raw_window_handle = window
+                    .window_handle()
+                    .map_err(|err| FramebufferError::SurfaceBuild(err.to_string()))?
+                    .as_raw();
+
+                let (not_current_gl_context, context_attributes) = unsafe {
+                    builder.create_context(&display, &gl_config, Some(raw_window_handle), share)?
+                };
+
+                let ctx_handle = not_current_gl_context
+                    .make_current(&surface)
+                    .map_err(FramebufferError::ContextCreation)?;
+                (ctx_handle, context_attributes)
+            };
+
+            let gl = Self::load_gl(&display)?;
+
+            (surface, ctx_handle, gl, context_attributes)
+        };
+
+        // not a window attribute, so only possible once the window exists
+        window.set_cursor_visible(builder.cursor_visible);
+        let mut framebuffer = builder.build_with_context(gl, surface, ctx_handle)?;
+        framebuffer.scale_factor = window.scale_factor();
+        framebuffer.context_attributes = context_attributes;
+        Ok((window, framebuffer))
+    }
+
+    /// Framebuffer on a context and surface owned by the application, see
+    /// [`FramebufferBuilder::build_with_context`].
+    pub fn from_existing(
+        gl: glow::Context,
+        surface: glutin::surface::Surface<WindowSurface>,
+        ctx: glutin::context::PossiblyCurrentContext,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, FramebufferError> {
+        FramebufferBuilder::new()
+            .size(width, height)
+            .build_with_context(gl, surface, ctx)
+    }
+
+    /// Headless framebuffer which is never shown, see [`FramebufferBuilder::build_headless`].
+    #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+    pub fn init_headless(width: usize, height: usize) -> Result<Self, FramebufferError> {
+        FramebufferBuilder::new()
+            .size(width, height)
+            .build_headless()
+    }
+
+    #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+    fn headless_from_builder(
+        builder: FramebufferBuilder<Format>,
+    ) -> Result<Self, FramebufferError> {
+        use glutin::api::egl::{device::Device, display::Display};
+        use glutin::config::ConfigSurfaceTypes;
+
+        // a device display needs neither a window nor a display server
+        let display = Device::query_devices()
+            .map_err(FramebufferError::ContextCreation)?
+            .find_map(|device| unsafe { Display::with_device(&device, None) }.ok())
+            .ok_or_else(|| {
+                FramebufferError::ConfigSelection("no EGL device with a display".to_owned())
+            })?;
+
+        let mut template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_surface_type(ConfigSurfaceTypes::empty());
+        if let Some(depth_bits) = builder.depth_bits {
+            template = template.with_depth_size(depth_bits);
+        }
+        if let Some(stencil_bits) = builder.stencil_bits {
+            template = template.with_stencil_size(stencil_bits);
+        }
+        let gl_config = unsafe { display.find_configs(template.build()) }
+            .map_err(|err| FramebufferError::ConfigSelection(err.to_string()))?
+            .next()
+            .ok_or_else(|| {
+                FramebufferError::ConfigSelection("no config without a surface".to_owned())
+            })?;
+        let (ctx_handle, context_attributes) =
+            unsafe { builder.create_context(&display, &gl_config, None, None)? };
+        let ctx_handle = ctx_handle
+            .make_current_surfaceless()
+            .map_err(FramebufferError::ContextCreation)?;
+        let gl = Self::load_gl(&display)?;
+
+        let surface_size = [builder.width as u32, builder.height as u32];
+        let mut framebuffer = Self::from_context(
+            builder,
+            None,
+            surface_size,
+            glutin::context::PossiblyCurrentContext::Egl(ctx_handle),
+            Config::Egl(gl_config),
+            gl,
+        )?;
+        framebuffer.context_attributes = context_attributes;
+        Ok(framebuffer)
+    }
+
+    fn load_gl(display: &impl GlDisplay) -> Result<glow::Context, FramebufferError> {
+        // glow loads lazily and would only fail on the first call, so probe a core function
+        if display.get_proc_address(c"glGetString").is_null() {
+            return Err(FramebufferError::ProcAddressLoading("glGetString"));
+        }
+        Ok(unsafe { glow::Context::from_loader_function_cstr(|s| display.get_proc_address(s)) })
+    }
+
+    /// Set up the objects used to upload and present the pixels on a current context.
+    fn from_context(
+        builder: FramebufferBuilder<Format>,
+        surface: Option<glutin::surface::Surface<WindowSurface>>,
+        surface_size: [u32; 2],
+        ctx_handle: glutin::context::PossiblyCurrentContext,
+        gl_config: Config,
+        mut gl: glow::Context,
+    ) -> Result<Self, FramebufferError> {
+        let config_info = ConfigInfo::from_config(&gl_config);
+        log!(debug, "OpenGL config: {config_info:?}");
+        let (width, height) = (builder.width, builder.height);
+        let capabilities = Capabilities::query(&gl);
+        log!(debug, "OpenGL capabilities: {capabilities:?}");
+        if builder.debug {
+            Self::install_debug_callback(&mut gl, &capabilities);
+        }
+        capabilities.check_size(width, height)?;
+        let srgb = builder.srgb && gl_config.srgb_capable();
+        if builder.srgb && !srgb {
+            log!(
+                warn,
+                "the picked config is not sRGB capable, presenting without sRGB"
+            );
+        }
+        let internal_format = builder.texture_internal_format(srgb)?;
+
+        let persistent_mapping = builder.persistent_mapping && capabilities.supports_persistent_map;
+        let objects = Self::setup_gl(
+            &gl,
+            width,
+            height,
+            &GlSetup {
+                capabilities: &capabilities,
+                fragment_shader: builder.fragment_shader.as_deref(),
+                origin: builder.origin,
+                wrap_mode: builder.wrap_mode,
+                filter: [builder.min_filter, builder.mag_filter],
+                internal_format,
+                pbo_ring: builder.pbo_ring,
+                persistent_mapping,
+                buffer_usage: builder.buffer_usage,
+                clear_color: builder.clear_color,
+            },
+        )?;
+
+        let mut framebuffer = Self {
+            surface,
+            ctx_handle,
+            gl_config,
+            context_attributes: ContextAttributesBuilder::new().build(None),
+            fragment_shader: builder.fragment_shader,
+            debug: builder.debug,
+            surface_size,
+            minimized: false,
+            occluded: false,
+            scale_factor: 1.0,
+            scaling: builder.scaling,
+            origin: builder.origin,
+            width,
+            height,
+            gl: Arc::new(gl),
+            pixel_buffers: objects.pixel_buffers,
+            current_buffer: Cell::new(0),
+            queued: VecDeque::new(),
+            backbuffer: Vec::new(),
+            orphan_buffers: builder.orphan_buffers,
+            buffer_usage: builder.buffer_usage,
+            persistent_mapping,
+            readable_buffers: false,
+            texture: objects.texture,
+            read_framebuffer: objects.read_framebuffer,
+            wrap_mode: builder.wrap_mode,
+            filter: [builder.min_filter, builder.mag_filter],
+            vao: objects.vao,
+            program: objects.program,
+            readback: None,
+            readback_generation: 0,
+            config_info,
+            capabilities,
+            clear_color: builder.clear_color,
+            transparent: builder.transparent,
+            srgb,
+            internal_format,
+            max_latency: builder.max_latency,
+            latency_fences: VecDeque::new(),
+            view: ([0.0, 0.0], 1.0),
+            view_location: objects.view_location,
+            transform: builder.transform,
+            transform_location: objects.transform_location,
+            allow_overscroll: builder.allow_overscroll,
+            clock: FrameClock::default(),
+            metrics: Metrics::default(),
+            sync_metrics: false,
+        };
+
+        if let Some(interval) = builder
+            .swap_interval
+            .filter(|_| framebuffer.surface.is_some())
+        {
+            if let Err(err) = framebuffer.set_swap_interval(interval) {
+                log!(warn, "{err}");
+            }
+        }
+
+        Ok(framebuffer)
+    }
+
+    /// Create the shader program, texture, vertex array, read framebuffer and pixel buffers of a
+    /// `width` by `height` framebuffer in the current context, and set its clear color.
+    ///
+    /// Only needs a loaded `gl`, so windowed, headless and reinitialized framebuffers all
+    /// share it.
+    fn setup_gl(
+        gl: &glow::Context,
+        width: usize,
+        height: usize,
+        setup: &GlSetup<'_>,
+    ) -> Result<GlObjects<Format>, FramebufferError> {
+        // uploads and the sampler setup call the direct state access functions
+        if !setup.capabilities.supports_direct_state_access {
+            let (major, minor) = setup.capabilities.version;
+            return Err(FramebufferError::UnsupportedVersion { major, minor });
+        }
+        let (program, view_location, transform_location) =
+            Self::create_program(gl, setup.fragment_shader, setup.origin)?;
+        let (vao, texture) = Self::create_storage(
+            gl,
+            width,
+            height,
+            setup.wrap_mode,
+            setup.filter,
+            setup.internal_format,
+        )?;
+        let read_framebuffer = Self::create_read_framebuffer(gl, texture)?;
+        let pixel_buffers = Self::create_upload_buffers(
+            gl,
+            setup.pbo_ring,
+            width * height,
+            setup.persistent_mapping,
+            false,
+            setup.buffer_usage,
+        )?;
+
+        unsafe {
+            let [r, g, b, a] = setup.clear_color;
+
+            gl.clear_color(r, g, b, a);
+            try_check!(gl);
+        }
+
+        Ok(GlObjects {
+            program,
+            view_location,
+            transform_location,
+            vao,
+            texture,
+            read_framebuffer,
+            pixel_buffers,
+        })
+    }
+
+    /// Route the driver's debug messages to the log.
+    fn install_debug_callback(gl: &mut glow::Context, capabilities: &Capabilities) {
+        if !capabilities.supports_debug_output {
+            log!(warn, "debug output is not supported");
+            return;
+        }
+        unsafe {
+            gl.enable(glow::DEBUG_OUTPUT);
+            // report messages from within the offending call, so they line up with the caller
+            gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+            gl.debug_message_callback(|_source, _kind, id, severity, message| match severity {
+                glow::DEBUG_SEVERITY_HIGH => log!(error, "OpenGL ({id}): {message}"),
+                glow::DEBUG_SEVERITY_MEDIUM => log!(warn, "OpenGL ({id}): {message}"),
+                glow::DEBUG_SEVERITY_LOW => log!(info, "OpenGL ({id}): {message}"),
+                _ => log!(debug, "OpenGL ({id}): {message}"),
+            });
+            check!(gl);
+        }
+    }
+
+    /// Compile and link the shader program, `None` selecting the default fragment shader.
+    fn create_program(
+        gl: &glow::Context,
+        fragment_shader: Option<&str>,
+        origin: Origin,
+    ) -> Result<
+        (
+            glow::Program,
+            Option<glow::UniformLocation>,
+            Option<glow::UniformLocation>,
+        ),
+        FramebufferError,
+    > {
+        let program = {
+            let vertex_shader =
+                Self::compile_shader(gl, Self::VERTEX_SHADER_SRC, ShaderStage::Vertex)?;
+            let fragment_source = fragment_shader.unwrap_or(Self::FRAGMENT_SHADER_SRC);
+            let fragment_shader =
+                match Self::compile_shader(gl, fragment_source, ShaderStage::Fragment) {
+                    Ok(shader) => shader,
+                    Err(err) => {
+                        unsafe { gl.delete_shader(vertex_shader) };
+                        return Err(err.into());
+                    }
+                };
+            Self::create_shader_program(gl, vertex_shader, fragment_shader)?
+        };
+        let view_location = unsafe { gl.get_uniform_location(program, "view") };
+        let transform_location = unsafe { gl.get_uniform_location(program, "transform") };
+        unsafe {
+            gl.use_program(Some(program));
+            let flip_y = gl.get_uniform_location(program, "flip_y");
+            gl.uniform_1_i32(flip_y.as_ref(), (origin == Origin::BottomLeft) as i32);
+            try_check!(gl);
+        }
+        Ok((program, view_location, transform_location))
+    }
+
+    /// Create the vertex array and the texture the pixels are uploaded to.
+    fn create_storage(
+        gl: &glow::Context,
+        width: usize,
+        height: usize,
+        wrap_mode: WrapMode,
+        filter: [Filter; 2],
+        internal_format: u32,
+    ) -> Result<(glow::VertexArray, glow::Texture), FramebufferError> {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .map_err(FramebufferError::ObjectCreation)?;
+            try_check!(gl);
+            gl.bind_vertex_array(Some(vao));
+            try_check!(gl);
+            let texture =
+                Self::create_texture(gl, width, height, wrap_mode, filter, internal_format)?;
+            try_check!(gl);
+            Ok((vao, texture))
+        }
+    }
+
+    fn compile_shader(
+        gl: &glow::Context,
+        source: &str,
+        stage: ShaderStage,
+    ) -> Result<glow::Shader, ShaderError> {
+        unsafe {
+            let shader = gl
+                .create_shader(stage.gl_enum())
+                .map_err(|log| ShaderError { stage, log })?;
+            gl.shader_source(shader, source);
+            gl.compile_shader(shader);
+
+            if !gl.get_shader_compile_status(shader) {
+                let log = gl.get_shader_info_log(shader);
+                gl.delete_shader(shader);
+                return Err(ShaderError { stage, log });
+            }
+            Ok(shader)
+        }
+    }
+
+    fn create_shader_program(
+        gl: &glow::Context,
+        vertex_shader: glow::Shader,
+        fragment_shader: glow::Shader,
+    ) -> Result<glow::Program, ProgramError> {
+        unsafe {
+            let program = gl.create_program().map_err(|log| ProgramError { log })?;
+            gl.attach_shader(program, vertex_shader);
+            gl.attach_shader(program, fragment_shader);
+            gl.link_program(program);
+
+            // the program keeps the compiled code, the shaders are no longer needed
+            gl.detach_shader(program, vertex_shader);
+            gl.detach_shader(program, fragment_shader);
+            gl.delete_shader(vertex_shader);
+            gl.delete_shader(fragment_shader);
+
+            if !gl.get_program_link_status(program) {
+                let log = gl.get_program_info_log(program);
+                gl.delete_program(program);
+                return Err(ProgramError { log });
+            }
+            Ok(program)
+        }
+    }
+
+    const VERTEX_SHADER_SRC: &str = r#"
+#version 330 core
+out vec2 TexCoord;
+// xy: offset of the visible region, z: zoom factor
+uniform vec3 view;
+// show the first row at the bottom
+uniform bool flip_y;
+// applied to the quad, the identity covers the viewport
+uniform mat4 transform;
+
+void main()
+{
+    // Define the quad vertices directly
+    vec3 vertices[4] = vec3[4] (
+        vec3(-1.0, -1.0, 0.0),  // bottom-left  (0)
+        vec3( 1.0, -1.0, 0.0),  // bottom-right (1)
+        vec3(-1.0,  1.0, 0.0),  // top-left     (2)
+        vec3( 1.0,  1.0, 0.0)   // top-right    (3)
+    );
+
+    // Texture coordinates corresponding to each vertex
+    vec2 texCoords[4] = vec2[4](
+        vec2(0.0, 1.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0) 
+    );
+
+    // Set gl_Position and pass the texture coordinates
+    gl_Position = transform * vec4(vertices[gl_VertexID].xyz, 1.0);
+    TexCoord = (texCoords[gl_VertexID] - 0.5) / view.z + 0.5 + view.xy;
+    if (flip_y) {
+        TexCoord.y = 1.0 - TexCoord.y;
+    }
+}
+"#;
+
+    const FRAGMENT_SHADER_SRC: &str = r#"
+#version 330 core
+out vec4 FragColor;
+in vec2 TexCoord;
+uniform sampler2D texture1;
+void main()
+{
+    FragColor = texture(texture1, TexCoord);
+}
+"#;
+
+    fn create_pixel_buffer(
+        gl: &glow::Context,
+        length: usize,
+        target: u32,
+        usage: u32,
+    ) -> Result<PixelBuffer<Format>, FramebufferError> {
+        unsafe {
+            let pbo = gl
+                .create_buffer()
+                .map_err(FramebufferError::ObjectCreation)?;
+            gl.bind_buffer(target, Some(pbo));
+            try_check!(gl);
+            gl.buffer_data_size(target, (length * std::mem::size_of::<Format>()) as _, usage);
+            try_check!(gl);
+            gl.bind_buffer(target, None);
+            try_check!(gl);
+            Ok(PixelBuffer {
+                raw_buffer: pbo,
+                length,
+                format: PhantomData,
+                persistent: None,
+                fence: Cell::new(None),
+            })
+        }
+    }
+
+    /// Pixel buffer with immutable storage which stays mapped until it is deleted.
+    ///
+    /// Only `readable` buffers may be read through the mapping, which can make writes to it
+    /// slower, so they are only created once [`Framebuffer::map`] needs them.
+    fn create_persistent_pixel_buffer(
+        gl: &glow::Context,
+        length: usize,
+        readable: bool,
+    ) -> Result<PixelBuffer<Format>, FramebufferError> {
+        let read_bit = if readable { glow::MAP_READ_BIT } else { 0 };
+        let flags =
+            glow::MAP_PERSISTENT_BIT | read_bit | glow::MAP_WRITE_BIT | glow::MAP_COHERENT_BIT;
+        let size = (length * std::mem::size_of::<Format>()) as _;
+
+        unsafe {
+            let pbo = gl
+                .create_buffer()
+                .map_err(FramebufferError::ObjectCreation)?;
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(pbo));
+            try_check!(gl);
+            gl.buffer_storage(glow::PIXEL_UNPACK_BUFFER, size, None, flags);
+            try_check!(gl);
+            let mapped_memory =
+                gl.map_buffer_range(glow::PIXEL_UNPACK_BUFFER, 0, size, flags) as *mut c_void;
+            try_check!(gl);
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, None);
+            try_check!(gl);
+
+            if mapped_memory.is_null() {
+                gl.delete_buffer(pbo);
+                return Err(FramebufferError::BufferMapping);
+            }
+
+            Ok(PixelBuffer {
+                raw_buffer: pbo,
+                length,
+                format: PhantomData,
+                persistent: Some(mapped_memory),
+                fence: Cell::new(None),
+            })
+        }
+    }
+
+    fn create_upload_buffers(
+        gl: &glow::Context,
+        count: usize,
+        length: usize,
+        persistent: bool,
+        readable: bool,
+        usage: BufferUsage,
+    ) -> Result<Vec<PixelBuffer<Format>>, FramebufferError> {
+        let mut buffers = Vec::with_capacity(count);
+        for _ in 0..count {
+            let buffer = if persistent {
+                Self::create_persistent_pixel_buffer(gl, length, readable)
+            } else {
+                Self::create_pixel_buffer(gl, length, glow::PIXEL_UNPACK_BUFFER, usage.gl_enum())
+            };
+            match buffer {
+                Ok(buffer) => buffers.push(buffer),
+                Err(err) => {
+                    // don't leak the buffers created so far
+                    for buffer in &buffers {
+                        buffer.delete(gl);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(buffers)
+    }
+
+    /// Whether the pixel buffers stay mapped across frames, see
+    /// [`FramebufferConfigExt::persistent_mapping`].
+    pub fn is_persistently_mapped(&self) -> bool {
+        self.persistent_mapping
+    }
+
+    /// Reallocate the framebuffer and the window surface for a new size, e.g. in response to
+    /// `WindowEvent::Resized`. See [`Framebuffer::resize_surface`] to keep the framebuffer size.
+    ///
+    /// The contents of the pixel buffer are lost and readbacks still in flight are cancelled.
+    /// Zero sizes, as reported for minimized windows, keep the current size and hide the
+    /// framebuffer until the next non-zero size, see [`Framebuffer::is_visible`]. Sizes above
+    /// [`Capabilities::max_texture_size`] fail with [`FramebufferError::InvalidSize`]. The
+    /// framebuffer is left as it was on any error, also if the new GL objects can't be created.
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        let (Some(surface_width), Some(surface_height)) = (
+            NonZeroU32::new(width as u32),
+            NonZeroU32::new(height as u32),
+        ) else {
+            self.minimized = true;
+            return Ok(());
+        };
+        self.capabilities.check_size(width, height)?;
+
+        // create the new objects before touching the old ones, so failing leaves them intact
+        let gl = &self.gl;
+        let pixel_buffers = Self::create_upload_buffers(
+            gl,
+            self.pixel_buffers.len(),
+            width * height,
+            self.persistent_mapping,
+            self.readable_buffers,
+            self.buffer_usage,
+        )?;
+        // texture storage is immutable, so the texture has to be recreated
+        let objects = Self::create_texture(
+            gl,
+            width,
+            height,
+            self.wrap_mode,
+            self.filter,
+            self.internal_format,
+        )
+        .and_then(|texture| {
+            Self::create_read_framebuffer(gl, texture)
+                .map(|framebuffer| (texture, framebuffer))
+                .inspect_err(|_| unsafe { gl.delete_texture(texture) })
+        });
+        let (texture, read_framebuffer) = match objects {
+            Ok(objects) => objects,
+            Err(err) => {
+                for buffer in &pixel_buffers {
+                    buffer.delete(gl);
+                }
+                return Err(err);
+            }
+        };
+
+        self.minimized = false;
+        if let Some(surface) = &self.surface {
+            surface.resize(&self.ctx_handle, surface_width, surface_height);
+        }
+        self.surface_size = [surface_width.get(), surface_height.get()];
+        if let Some(readback) = self.readback.take() {
+            readback.delete(gl);
+        }
+        for buffer in &self.pixel_buffers {
+            buffer.delete(gl);
+        }
+        unsafe {
+            gl.delete_framebuffer(self.read_framebuffer);
+            gl.delete_texture(self.texture);
+            check!(gl);
+        }
+        self.pixel_buffers = pixel_buffers;
+        self.texture = texture;
+        self.read_framebuffer = read_framebuffer;
+        self.width = width;
+        self.height = height;
+        self.current_buffer.set(0);
+        self.queued.clear();
+        Ok(())
+    }
+
+    /// Resize only the window surface, keeping the framebuffer size.
+    ///
+    /// The size is in physical pixels, as reported by `WindowEvent::Resized`. The pixels are
+    /// then scaled to the new size according to the [`ScalingMode`]. The framebuffer's context
+    /// must be current, see [`Framebuffer::make_current`].
+    pub fn resize_surface(&mut self, width: usize, height: usize) {
+        self.debug_assert_current();
+        let (Some(surface_width), Some(surface_height)) = (
+            NonZeroU32::new(width as u32),
+            NonZeroU32::new(height as u32),
+        ) else {
+            self.minimized = true;
+            return;
+        };
+        if let Some(surface) = &self.surface {
+            surface.resize(&self.ctx_handle, surface_width, surface_height);
+        }
+        self.surface_size = [surface_width.get(), surface_height.get()];
+        self.minimized = false;
+    }
+
+    /// Switch the framebuffer's `window` into fullscreen, or back with `None`.
+    ///
+    /// Once the transition is done the window reports its new size with
+    /// `WindowEvent::Resized`, which like any other resize goes to [`Framebuffer::resize`] to
+    /// reallocate the pixels for the screen, or to [`Framebuffer::resize_surface`] to scale them.
+    /// Platforms which change the size right away have the surface resized here already, so
+    /// the next frame covers the screen. The context must be current like for
+    /// `resize_surface`.
+    pub fn set_fullscreen(
+        &mut self,
+        window: &Window,
+        fullscreen: Option<winit::window::Fullscreen>,
+    ) {
+        window.set_fullscreen(fullscreen);
+        let size = window.inner_size();
+        if [size.width, size.height] != self.surface_size {
+            self.resize_surface(size.width as usize, size.height as usize);
+        }
+    }
+
+    /// React to `WindowEvent::ScaleFactorChanged`, e.g. when the window moves to a monitor with
+    /// a different DPI.
+    ///
+    /// `new_size` is the window's physical size at the new scale, i.e. `window.inner_size()`
+    /// once the event is handled. Only the surface is resized, the framebuffer keeps its
+    /// resolution and is scaled to the new surface by the [`ScalingMode`], so modes other than
+    /// [`ScalingMode::Stretch`] may letterbox it. Applications which want the pixels to follow
+    /// the DPI call [`Framebuffer::resize`] with the size of the following `WindowEvent::Resized`
+    /// as well. The context must be current like for `resize_surface`.
+    pub fn handle_scale_change(&mut self, scale_factor: f64, new_size: PhysicalSize<u32>) {
+        self.scale_factor = scale_factor;
+        self.resize_surface(new_size.width as usize, new_size.height as usize);
+    }
+
+    /// Record whether the window is hidden from view, as reported by `WindowEvent::Occluded`.
+    ///
+    /// Occluded framebuffers skip [`Framebuffer::draw`], see [`Framebuffer::is_visible`].
+    pub fn set_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+    }
+
+    /// Whether frames drawn now would be seen.
+    ///
+    /// False after resizing to a zero size, as reported for minimized windows, until the next
+    /// non-zero size, and while the window is occluded, see [`Framebuffer::set_occluded`].
+    pub fn is_visible(&self) -> bool {
+        !self.minimized && !self.occluded
+    }
+
+    pub fn set_scaling(&mut self, scaling: ScalingMode) {
+        self.scaling = scaling;
+    }
+
+    pub fn scaling(&self) -> ScalingMode {
+        self.scaling
+    }
+
+    /// Map the pixels to client memory for direct access.
+    ///
+    /// The guard dereferences to the pixel slice, which starts out holding the frame currently in
+    /// the texture, so it can be blended with or changed in place. Dropping the guard uploads the
+    /// pixels to the texture, where they stay until the next upload, e.g. by
+    /// [`Framebuffer::draw`].
+    ///
+    /// Both ends synchronize with the GPU: mapping waits until the previous frame has been copied
+    /// back, unmapping queues the upload. The guard borrows the framebuffer mutably, so only one
+    /// mapping can exist at a time.
+    ///
+    /// ```no_run
+    /// # fn map(framebuffer: &mut cpf::Framebuffer<[u8; 4]>) -> Result<(), cpf::FramebufferError> {
+    /// let mut pixels = framebuffer.map()?;
+    /// pixels[0] = [255, 0, 0, 255];
+    /// pixels[1..].fill([0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn map(&mut self) -> Result<MMap<'_, Format>, FramebufferError> {
+        self.make_current_if_needed()?;
+        self.queued.clear();
+        if self.persistent_mapping && !self.readable_buffers {
+            self.recreate_readable_buffers()?;
+        }
+        self.copy_texture_to_buffer()?;
+        MMap::new(self)
+    }
+
+    /// Replace the persistent pixel buffers with ones that can be read through their mapping.
+    ///
+    /// Their storage is immutable, so the read access can't be added to the existing buffers.
+    fn recreate_readable_buffers(&mut self) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        let pixel_buffers = Self::create_upload_buffers(
+            gl,
+            self.pixel_buffers.len(),
+            self.width * self.height,
+            true,
+            true,
+            self.buffer_usage,
+        )?;
+        for buffer in &self.pixel_buffers {
+            buffer.delete(gl);
+        }
+        self.pixel_buffers = pixel_buffers;
+        self.current_buffer.set(0);
+        self.readable_buffers = true;
+        Ok(())
+    }
+
+    /// Set the pixel at `(x, y)` and upload only it.
+    ///
+    /// `y` counts rows of the pixel buffer, so whether row 0 is shown at the top or the bottom
+    /// of the window follows [`FramebufferBuilder::origin`].
+    /// Every call maps and uploads on its own, which suits a few scattered pixels. For many of
+    /// them, write them into [`Framebuffer::backbuffer_mut`] and upload it at once with
+    /// [`Framebuffer::draw_from_backbuffer`]. The rest of the texture is unchanged, and the
+    /// pixel is shown by the next [`Framebuffer::present`]. Fails with
+    /// [`FramebufferError::OutOfBounds`] for coordinates outside of the framebuffer.
+    pub fn set_pixel(&mut self, x: usize, y: usize, pixel: Format) -> Result<(), FramebufferError> {
+        if x >= self.width || y >= self.height {
+            return Err(FramebufferError::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        let mut guard = self.map_rect([x, y, 1, 1])?;
+        guard[x] = pixel;
+        Ok(())
+    }
+
+    /// Map only the rows covering the region `[x, y, width, height]` (in pixels) for writing,
+    /// and upload only the region on drop.
+    ///
+    /// For partial updates this keeps both the mapping and the upload to the changed pixels.
+    /// The guard holds full rows starting at row `y`, so the pixel at `(x, y)` is at index `x`.
+    /// Their previous contents are undefined, while the rest of the texture is unchanged. The
+    /// region is clipped to the frame. Rows count from the start of the pixel buffer, so which
+    /// corner of the window the region is measured from follows [`FramebufferBuilder::origin`].
+    pub fn map_rect(&mut self, rect: [usize; 4]) -> Result<MMap<'_, Format>, FramebufferError> {
+        self.make_current_if_needed()?;
+        self.queued.clear();
+        let [x, y, width, height] = rect;
+        let y = y.min(self.height);
+        let rows = height.min(self.height - y);
+        let mut guard = MMap::new_range(self, y * self.width, rows * self.width)?;
+        if guard.length > 0 {
+            guard.set_dirty_rect([x, y, width, rows]);
+        }
+        Ok(guard)
+    }
+
+    /// Transfer the contents of a pixel buffer to the texture.
+    fn upload_texture(&self, buffer: &PixelBuffer<Format>, rect: Option<[usize; 4]>) {
+        let [x, y, width, height] = match rect {
+            Some([x, y, width, height]) => {
+                let (x, y) = (x.min(self.width), y.min(self.height));
+                [x, y, width.min(self.width - x), height.min(self.height - y)]
+            }
+            None => [0, 0, self.width, self.height],
+        };
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let gl = &self.gl;
+        unsafe {
+            gl.active_texture(glow::TEXTURE0);
+            check!(gl);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            check!(gl);
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(buffer.raw_buffer));
+            check!(gl);
+            // rows are tightly packed, which for e.g. 3 byte pixels need not be a multiple of 4
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            check!(gl);
+            // the region is cut out of full width rows
+            gl.pixel_store_i32(glow::UNPACK_ROW_LENGTH, self.width as _);
+            check!(gl);
+
+            // Transfer data from the PBO to the texture
+            let offset = (y * self.width + x) * std::mem::size_of::<Format>();
+            gl.texture_sub_image_2d(
+                self.texture,
+                0, // mip level
+                x as _,
+                y as _,
+                width as _,
+                height as _,
+                Format::GL_FORMAT,
+                Format::GL_TYPE,
+                glow::PixelUnpackData::BufferOffset(offset as _),
+            );
+            check!(gl);
+            gl.pixel_store_i32(glow::UNPACK_ROW_LENGTH, 0);
+            check!(gl);
+            // uploads from client memory by others would otherwise read from the buffer instead
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, None);
+            check!(gl);
+        }
+    }
+
+    /// Window region `[x, y, width, height]` (in pixels, origin bottom-left) covered by the quad.
+    ///
+    /// `draw` clears the entire window before enabling the scissor test for this region, so any
+    /// area outside of it (e.g. bars left over by scaling) always shows the clear color while the
+    /// blit itself can never touch pixels it does not own.
+    fn content_region(&self) -> [i32; 4] {
+        let [width, height] = self.surface_size;
+        self.fit_region([0, 0, width as i32, height as i32])
+    }
+
+    /// Part of the window region `[x, y, width, height]` covered by the quad when fit into it
+    /// according to the [`ScalingMode`].
+    fn fit_region(&self, [x, y, region_width, region_height]: [i32; 4]) -> [i32; 4] {
+        let (region_width, region_height) = (region_width as f32, region_height as f32);
+        let (width, height) = (self.width as f32, self.height as f32);
+        let fit = (region_width / width).min(region_height / height);
+        let scale = match self.scaling {
+            ScalingMode::Stretch => {
+                return [x, y, region_width as i32, region_height as i32];
+            }
+            ScalingMode::IntegerCenter if fit >= 1.0 => fit.floor(),
+            ScalingMode::IntegerCenter | ScalingMode::FitContain => fit,
+        };
+        let (width, height) = ((width * scale).round(), (height * scale).round());
+        [
+            x + ((region_width - width) / 2.0) as i32,
+            y + ((region_height - height) / 2.0) as i32,
+            width as i32,
+            height as i32,
+        ]
+    }
+
+    /// Framebuffer object with `texture` attached, so its contents can be read back.
+    fn create_read_framebuffer(
+        gl: &glow::Context,
+        texture: glow::Texture,
+    ) -> Result<glow::Framebuffer, FramebufferError> {
+        unsafe {
+            let framebuffer = gl
+                .create_framebuffer()
+                .map_err(FramebufferError::ObjectCreation)?;
+            try_check!(gl);
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(framebuffer));
+            try_check!(gl);
+            gl.framebuffer_texture_2d(
+                glow::READ_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            try_check!(gl);
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+            try_check!(gl);
+            Ok(framebuffer)
+        }
+    }
+
+    /// Read the texture back into the current pixel buffer, without a round trip through client
+    /// memory.
+    fn copy_texture_to_buffer(&self) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        let buffer = &self.pixel_buffers[self.current_buffer.get()];
+        // the GPU must not overwrite a persistently mapped buffer while it is still uploaded from
+        buffer.wait_for_upload(gl)?;
+        let result = unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.read_framebuffer));
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(buffer.raw_buffer));
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            gl.read_pixels(
+                0,
+                0,
+                self.width as _,
+                self.height as _,
+                Format::GL_FORMAT,
+                Format::GL_TYPE,
+                glow::PixelPackData::BufferOffset(0),
+            );
+            let result = check_gl(gl, concat!(file!(), ":", line!()));
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+            result
+        };
+        // persistent mappings see the copy once the fence is signaled
+        buffer.fence_upload(gl);
+        result?;
+        Ok(())
+    }
+
+    fn create_texture(
+        gl: &glow::Context,
+        width: usize,
+        height: usize,
+        wrap_mode: WrapMode,
+        [min_filter, mag_filter]: [Filter; 2],
+        internal_format: u32,
+    ) -> Result<glow::Texture, FramebufferError> {
+        unsafe {
+            let texture = gl
+                .create_texture()
+                .map_err(FramebufferError::ObjectCreation)?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            try_check!(gl);
+            gl.texture_storage_2d(texture, 1, internal_format, width as i32, height as i32);
+            try_check!(gl);
+            gl.texture_parameter_i32(
+                texture,
+                glow::TEXTURE_MIN_FILTER,
+                min_filter.gl_enum() as i32,
+            );
+            try_check!(gl);
+            gl.texture_parameter_i32(
+                texture,
+                glow::TEXTURE_MAG_FILTER,
+                mag_filter.gl_enum() as i32,
+            );
+            try_check!(gl);
+            gl.texture_parameter_i32(texture, glow::TEXTURE_WRAP_S, wrap_mode.gl_enum() as i32);
+            try_check!(gl);
+            gl.texture_parameter_i32(texture, glow::TEXTURE_WRAP_T, wrap_mode.gl_enum() as i32);
+            try_check!(gl);
+            let [r, g, b, a] = Format::SWIZZLE;
+            gl.texture_parameter_i32(texture, glow::TEXTURE_SWIZZLE_R, r as i32);
+            try_check!(gl);
+            gl.texture_parameter_i32(texture, glow::TEXTURE_SWIZZLE_G, g as i32);
+            try_check!(gl);
+            gl.texture_parameter_i32(texture, glow::TEXTURE_SWIZZLE_B, b as i32);
+            try_check!(gl);
+            gl.texture_parameter_i32(texture, glow::TEXTURE_SWIZZLE_A, a as i32);
+            try_check!(gl);
+            Ok(texture)
+        }
+    }
+}
+
+impl<Format: PixelFormat> Framebuffer<Format> {
+    /// Paint and present a frame, see [`Framebuffer::update`] and [`Framebuffer::present`].
+    ///
+    /// Nothing is painted or presented while the framebuffer is not
+    /// [visible](Framebuffer::is_visible), which returns [`PaintOutcome::Hidden`]. Fails
+    /// with [`FramebufferError::ContextLost`] if the context is gone, see
+    /// [`Framebuffer::reinit`].
+    pub fn draw(
+        &mut self,
+        painter: &mut impl Painter<Pixel = Format>,
+    ) -> Result<PaintOutcome, FramebufferError> {
+        if !self.is_visible() {
+            return Ok(PaintOutcome::Hidden);
+        }
+        let result = self.update(painter).and_then(|outcome| {
+            self.present()?;
+            Ok(outcome)
+        });
+        result.map_err(FramebufferError::detect_context_loss)
+    }
+
+    /// Replace a lost context with a new one on the same display and surface, and recreate
+    /// the objects the pixels are uploaded to and presented with.
+    ///
+    /// Call this after [`FramebufferError::ContextLost`], then draw the next frame as usual.
+    /// The pixels, queued frames and readbacks in flight are gone, as is anything else the
+    /// application created in the old context, e.g. an overlay from the `ui` module. Settings
+    /// of the context itself, like the swap interval, have to be applied again. Framebuffers
+    /// built with [`FramebufferBuilder::build_with_context`] get a context with the default
+    /// attributes.
+    pub fn reinit(&mut self) -> Result<(), FramebufferError> {
+        let display = self.gl_config.display();
+        let ctx = unsafe { display.create_context(&self.gl_config, &self.context_attributes) }
+            .map_err(FramebufferError::ContextCreation)?;
+        let ctx_handle = match &self.surface {
+            Some(surface) => ctx.make_current(surface),
+            None => Self::into_current_surfaceless(ctx),
+        }
+        .map_err(FramebufferError::ContextCreation)?;
+        let mut gl = Self::load_gl(&display)?;
+
+        let capabilities = Capabilities::query(&gl);
+        if self.debug {
+            Self::install_debug_callback(&mut gl, &capabilities);
+        }
+        let persistent_mapping = self.persistent_mapping && capabilities.supports_persistent_map;
+        let objects = Self::setup_gl(
+            &gl,
+            self.width,
+            self.height,
+            &GlSetup {
+                capabilities: &capabilities,
+                fragment_shader: self.fragment_shader.as_deref(),
+                origin: self.origin,
+                wrap_mode: self.wrap_mode,
+                filter: self.filter,
+                internal_format: self.internal_format,
+                pbo_ring: self.pixel_buffers.len(),
+                persistent_mapping,
+                buffer_usage: self.buffer_usage,
+                clear_color: self.clear_color,
+            },
+        )?;
+
+        // the old objects went with the old context, there is nothing left to delete
+        self.ctx_handle = ctx_handle;
+        self.gl = Arc::new(gl);
+        self.capabilities = capabilities;
+        self.program = objects.program;
+        self.view_location = objects.view_location;
+        self.transform_location = objects.transform_location;
+        self.persistent_mapping = persistent_mapping;
+        self.readable_buffers = false;
+        self.vao = objects.vao;
+        self.pixel_buffers = objects.pixel_buffers;
+        self.current_buffer.set(0);
+        self.texture = objects.texture;
+        self.read_framebuffer = objects.read_framebuffer;
+        self.readback = None;
+        self.queued.clear();
+        self.latency_fences.clear();
+        Ok(())
+    }
+
+    /// Let the painter write the next frame and upload it to the texture.
+    ///
+    /// Custom GL calls issued between this and [`Framebuffer::present`] can e.g. render into
+    /// the texture before it is shown.
+    pub fn update(
+        &mut self,
+        painter: &mut impl Painter<Pixel = Format>,
+    ) -> Result<PaintOutcome, FramebufferError> {
+        self.make_current_if_needed()?;
+        let frame = self.clock.tick(self.width, self.height);
+        self.queued.clear();
+
+        // note: scope here is important as dropping the guard unmaps the buffer and uploads the
+        // pixels
+        let map_start = Instant::now();
+        let mut guard = if self.orphan_buffers {
+            MMap::orphaned(self)?
+        } else {
+            MMap::new_write(self)?
+        };
+        let paint_start = Instant::now();
+        let outcome = painter.paint_frame(&mut guard, &frame);
+        if let Some(rect) = painter.dirty_rect() {
+            guard.set_dirty_rect(rect);
+        }
+        let paint_end = Instant::now();
+        let unmap_start = Instant::now();
+        drop(guard);
+        self.finish_for_metrics();
+        self.metrics.paint = paint_end - paint_start;
+        self.metrics.upload = (paint_start - map_start) + unmap_start.elapsed();
+
+        // a common mistake when switching to a transparent window, check only the first frame
+        // as reading the texture back stalls
+        if cfg!(debug_assertions) && self.transparent && frame.frame == 0 {
+            self.warn_if_transparent(painter.dirty_rect())?;
+        }
+        Ok(outcome)
+    }
+
+    /// Warn if the uploaded pixels inside of `rect`, the whole frame for `None`, are all fully
+    /// transparent.
+    ///
+    /// Reads the texture back, as the write-only mapping the pixels were painted into can't be
+    /// read, and pixels outside of the painter's dirty rect are undefined.
+    fn warn_if_transparent(&self, rect: Option<[usize; 4]>) -> Result<(), FramebufferError> {
+        let [x, y, width, height] = rect.unwrap_or([0, 0, self.width, self.height]);
+        let (x, y) = (x.min(self.width), y.min(self.height));
+        let (width, height) = (width.min(self.width - x), height.min(self.height - y));
+        let pixels = self.read_pixels()?;
+        let transparent = pixels
+            .chunks_exact(self.width)
+            .skip(y)
+            .take(height)
+            .flat_map(|row| &row[x..x + width])
+            .all(|pixel| pixel.to_rgba8()[3] == 0);
+        if transparent && width > 0 && height > 0 {
+            log!(
+                warn,
+                "the first frame is fully transparent, opaque pixels need an alpha of 255"
+            );
+        }
+        Ok(())
+    }
+
+    /// Durations of the phases of the last frame, see [`Metrics`].
+    pub fn last_metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// Wait for the GPU to finish the upload and the presentation of each frame, so their
+    /// [`Metrics`] include the GPU's work.
+    ///
+    /// This stalls the pipeline and costs throughput, only turn it on while profiling. Off by
+    /// default.
+    pub fn set_sync_metrics(&mut self, sync: bool) {
+        self.sync_metrics = sync;
+    }
+
+    fn finish_for_metrics(&self) {
+        if self.sync_metrics {
+            unsafe {
+                self.gl.finish();
+                check!(self.gl);
+            }
+        }
+    }
+
+    /// Upload pixels produced elsewhere, e.g. a decoded video frame or a frame painted on
+    /// another thread, and present them without going through a [`Painter`].
+    ///
+    /// `pixels` has to hold exactly `width * height` pixels in row-major order, otherwise
+    /// [`FramebufferError::BufferSize`] is returned. See [`frame_channel`] for handing frames
+    /// over from a worker thread.
+    pub fn present_slice(&mut self, pixels: &[Format]) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        if pixels.len() != self.width * self.height {
+            return Err(FramebufferError::BufferSize {
+                expected: self.width * self.height,
+                actual: pixels.len(),
+            });
+        }
+
+        self.queued.clear();
+        let index = self.current_buffer.get();
+        self.write_pixel_buffer(index, pixels)?;
+        self.upload_pixel_buffer(index);
+        self.present()
+    }
+
+    /// Pixels in client memory which [`Framebuffer::draw_from_backbuffer`] uploads, for painting
+    /// without a current context or a mapped pixel buffer.
+    ///
+    /// Allocated on the first call and kept between frames, so only the changed pixels have to
+    /// be written. Starts out transparent black, as it does again after a resize.
+    pub fn backbuffer_mut(&mut self) -> &mut [Format] {
+        let size = self.width * self.height;
+        if self.backbuffer.len() != size {
+            self.backbuffer.clear();
+            self.backbuffer
+                .resize(size, Format::from_rgba8([0, 0, 0, 0]));
+        }
+        &mut self.backbuffer
+    }
+
+    /// Upload the [backbuffer](Framebuffer::backbuffer_mut) and present it.
+    ///
+    /// Costs a copy of the whole frame, in exchange the painting is decoupled from OpenGL.
+    pub fn draw_from_backbuffer(&mut self) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        self.backbuffer_mut();
+
+        self.queued.clear();
+        let index = self.current_buffer.get();
+        self.write_pixel_buffer(index, &self.backbuffer)?;
+        self.upload_pixel_buffer(index);
+        self.present()
+    }
+
+    /// Like [`Framebuffer::present_slice`], for pixels of another format which are converted
+    /// while they are written to the pixel buffer, see [`util::convert_into`].
+    ///
+    /// Lets e.g. a source of RGB pixels be shown by an RGBA framebuffer without an intermediate
+    /// copy. Conversions go through 8 bit RGBA, so more precise formats lose precision.
+    pub fn present_converted<Src: PixelFormat>(
+        &mut self,
+        pixels: &[Src],
+    ) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        if pixels.len() != self.width * self.height {
+            return Err(FramebufferError::BufferSize {
+                expected: self.width * self.height,
+                actual: pixels.len(),
+            });
+        }
+
+        self.queued.clear();
+        {
+            // dropping the guard uploads the converted pixels
+            let mut guard = if self.orphan_buffers {
+                MMap::orphaned(self)?
+            } else {
+                MMap::new_write(self)?
+            };
+            util::convert_into(pixels, &mut guard);
+        }
+        self.present()
+    }
+
+    /// Like [`Framebuffer::present_slice`], for pixels whose rows are `row_length` pixels apart.
+    ///
+    /// Shows the top-left `width` by `height` region of a larger image, or an image whose rows
+    /// are padded, without repacking it. The rows are read by OpenGL straight from `pixels`,
+    /// which has to reach at least to the end of the last row's `width` pixels. Fails with
+    /// [`FramebufferError::RowLength`] if `row_length` is smaller than the width.
+    pub fn present_slice_with_stride(
+        &mut self,
+        pixels: &[Format],
+        row_length: usize,
+    ) -> Result<(), FramebufferError> {
+        if row_length < self.width {
+            return Err(FramebufferError::RowLength {
+                row_length,
+                width: self.width,
+            });
+        }
+        let expected = row_length * (self.height - 1) + self.width;
+        if pixels.len() < expected {
+            return Err(FramebufferError::BufferSize {
+                expected,
+                actual: pixels.len(),
+            });
+        }
+        self.make_current_if_needed()?;
+        self.queued.clear();
+
+        let gl = &self.gl;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(pixels.as_ptr() as *const u8, size_of_val(pixels))
+        };
+        unsafe {
+            // read from client memory rather than a pixel buffer
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, None);
+            try_check!(gl);
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            try_check!(gl);
+            gl.pixel_store_i32(glow::UNPACK_ROW_LENGTH, row_length as _);
+            try_check!(gl);
+            gl.texture_sub_image_2d(
+                self.texture,
+                0,
+                0,
+                0,
+                self.width as _,
+                self.height as _,
+                Format::GL_FORMAT,
+                Format::GL_TYPE,
+                glow::PixelUnpackData::Slice(bytes),
+            );
+            let result = check_gl(gl, concat!(file!(), ":", line!()));
+            gl.pixel_store_i32(glow::UNPACK_ROW_LENGTH, 0);
+            result?;
+        }
+        self.present()
+    }
+
+    /// Copy a frame into the next free pixel buffer, to be shown by
+    /// [`Framebuffer::present_next`].
+    ///
+    /// Lets e.g. a video player decode frames as fast as they come and present them at the
+    /// display's pace. Frames queue up in the framebuffer's ring of pixel buffers, so as many
+    /// frames as there are buffers, 2 unless set with [`FramebufferBuilder::pbo_ring`], can be
+    /// queued without any extra memory. Returns `false` without copying if all of them hold
+    /// queued frames. Fails like [`Framebuffer::present_slice`] if the frame has the wrong size.
+    ///
+    /// Drawing in any other way, e.g. with [`Framebuffer::update`], and resizing discard the
+    /// queued frames.
+    pub fn enqueue_frame(&mut self, pixels: &[Format]) -> Result<bool, FramebufferError> {
+        self.make_current_if_needed()?;
+        if pixels.len() != self.width * self.height {
+            return Err(FramebufferError::BufferSize {
+                expected: self.width * self.height,
+                actual: pixels.len(),
+            });
+        }
+        if self.queued.len() == self.pixel_buffers.len() {
+            return Ok(false);
+        }
+        let index = match self.queued.back() {
+            Some(last) => (last + 1) % self.pixel_buffers.len(),
+            None => self.current_buffer.get(),
+        };
+        self.write_pixel_buffer(index, pixels)?;
+        self.queued.push_back(index);
+        Ok(true)
+    }
+
+    /// Upload and present the oldest frame queued with [`Framebuffer::enqueue_frame`].
+    ///
+    /// Returns `false` without presenting anything if the queue is empty.
+    pub fn present_next(&mut self) -> Result<bool, FramebufferError> {
+        self.make_current_if_needed()?;
+        let Some(index) = self.queued.pop_front() else {
+            return Ok(false);
+        };
+        self.upload_pixel_buffer(index);
+        self.present()?;
+        Ok(true)
+    }
+
+    /// Number of frames waiting for [`Framebuffer::present_next`].
+    pub fn queued_frames(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Copy `pixels` into the pixel buffer at `index` without uploading them.
+    fn write_pixel_buffer(&self, index: usize, pixels: &[Format]) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        let buffer = &self.pixel_buffers[index];
+        if let Some(mapped_memory) = buffer.persistent {
+            // immutable storage can only be written through its mapping
+            buffer.wait_for_upload(gl)?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    pixels.as_ptr(),
+                    mapped_memory as *mut Format,
+                    pixels.len(),
+                );
+                gl.memory_barrier(glow::CLIENT_MAPPED_BUFFER_BARRIER_BIT);
+                try_check!(gl);
+            }
+            return Ok(());
+        }
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(pixels.as_ptr() as *const u8, size_of_val(pixels))
+        };
+        unsafe {
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(buffer.raw_buffer));
+            try_check!(gl);
+            if self.orphan_buffers {
+                let usage = self.buffer_usage.gl_enum();
+                gl.buffer_data_u8_slice(glow::PIXEL_UNPACK_BUFFER, bytes, usage);
+            } else {
+                gl.buffer_sub_data_u8_slice(glow::PIXEL_UNPACK_BUFFER, 0, bytes);
+            }
+            try_check!(gl);
+            gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, None);
+            try_check!(gl);
+        }
+        Ok(())
+    }
+
+    /// Upload the current pixel buffer, see [`Framebuffer::pixel_buffer`], to the texture
+    /// without painting.
+    ///
+    /// For pixels written into the buffer by other GL code, e.g. a compute shader. Uploads and
+    /// presenting are separate steps: [`Framebuffer::update`] paints and uploads, while
+    /// [`Framebuffer::blit`] and [`Framebuffer::present`] only draw the texture, as does custom
+    /// code sampling [`Framebuffer::texture`]. Static content is thus uploaded once and drawn as
+    /// often as needed. Drops the frames queued with [`Framebuffer::enqueue_frame`].
+    pub fn upload_to_texture(&mut self) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        self.queued.clear();
+        self.upload_pixel_buffer(self.current_buffer.get());
+        Ok(())
+    }
+
+    /// Upload the whole pixel buffer at `index` and have the next frame use the one after it.
+    fn upload_pixel_buffer(&self, index: usize) {
+        let buffer = &self.pixel_buffers[index];
+        self.upload_texture(buffer, None);
+        buffer.fence_upload(&self.gl);
+        self.current_buffer
+            .set((index + 1) % self.pixel_buffers.len());
+    }
+
+    /// Draw the texture to the window and swap buffers, see [`Framebuffer::blit`] and
+    /// [`Framebuffer::swap_buffers`].
+    ///
+    /// Fails with [`FramebufferError::Headless`] for headless framebuffers, which have nothing to
+    /// present to.
+    pub fn present(&mut self) -> Result<(), FramebufferError> {
+        let start = Instant::now();
+        self.blit()?;
+        self.swap_buffers()?;
+        self.finish_for_metrics();
+        self.metrics.present = start.elapsed();
+        Ok(())
+    }
+
+    /// Fill the window with the clear color and show it, without drawing the pixels.
+    ///
+    /// Useful while there is nothing to show yet, or for apps that repaint only on demand. The
+    /// uploaded pixels are kept and shown again by the next [`Framebuffer::present`].
+    pub fn clear(&mut self) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        let gl = &self.gl;
+        if self.surface.is_none() {
+            return Err(FramebufferError::Headless);
+        }
+        unsafe {
+            gl.disable(glow::SCISSOR_TEST);
+            try_check!(gl);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            try_check!(gl);
+        }
+        self.swap_buffers()
+    }
+
+    /// Draw the texture to the window's back buffer without showing it yet.
+    ///
+    /// Anything drawn after this and before [`Framebuffer::swap_buffers`], e.g. a UI, ends up on
+    /// top of the pixels.
+    pub fn blit(&mut self) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        let gl = &self.gl;
+        if self.surface.is_none() {
+            return Err(FramebufferError::Headless);
+        }
+
+        // clear the whole window so that anything outside of the content region shows the clear
+        // color
+        unsafe {
+            gl.disable(glow::SCISSOR_TEST);
+            try_check!(gl);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            try_check!(gl);
+        }
+
+        self.draw_quad(self.content_region())
+    }
+
+    /// Draw the texture into the window region `[x, y, width, height]` (in pixels, origin
+    /// bottom-left) of the back buffer, without clearing anything.
+    ///
+    /// The pixels are fit into the region according to the [`ScalingMode`], and the rest of
+    /// the window is left as it is. This tiles a window with several views, e.g. one per
+    /// quadrant at different [zooms](Framebuffer::set_view): clear once, e.g. with
+    /// `gl.clear`, draw each region and finish with [`Framebuffer::swap_buffers`].
+    ///
+    /// The framebuffer draws into its own window. Other framebuffers can't draw into it, as each
+    /// is current with its own surface, but their textures can be sampled by custom GL code
+    /// with [`Framebuffer::texture`] if they share objects with this context, see
+    /// [`Framebuffer::init_shared`].
+    pub fn blit_into(&mut self, region: [i32; 4]) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        if self.surface.is_none() {
+            return Err(FramebufferError::Headless);
+        }
+        self.draw_quad(self.fit_region(region))
+    }
+
+    /// Draw the quad covering window `region`, with the blit confined to it.
+    fn draw_quad(&self, region: [i32; 4]) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        unsafe {
+            // stretch the quad over, and confine the blit to, the pixels covered by the framebuffer
+            let [x, y, width, height] = region;
+            gl.viewport(x, y, width, height);
+            try_check!(gl);
+            gl.enable(glow::SCISSOR_TEST);
+            try_check!(gl);
+            gl.scissor(x, y, width, height);
+            try_check!(gl);
+
+            gl.use_program(Some(self.program));
+            try_check!(gl);
+            let ([x, y], zoom) = self.view;
+            gl.uniform_3_f32(self.view_location.as_ref(), x, y, zoom);
+            try_check!(gl);
+            gl.uniform_matrix_4_f32_slice(
+                self.transform_location.as_ref(),
+                false,
+                self.transform.as_flattened(),
+            );
+            try_check!(gl);
+            gl.active_texture(glow::TEXTURE0);
+            try_check!(gl);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            try_check!(gl);
+            gl.bind_vertex_array(Some(self.vao));
+            try_check!(gl);
+            if self.srgb {
+                gl.enable(glow::FRAMEBUFFER_SRGB);
+                try_check!(gl);
+            }
+            if self.transparent {
+                gl.enable(glow::BLEND);
+                try_check!(gl);
+                // compositors expect premultiplied colors, so keep the source alpha as is
+                gl.blend_func_separate(
+                    glow::SRC_ALPHA,
+                    glow::ONE_MINUS_SRC_ALPHA,
+                    glow::ONE,
+                    glow::ONE_MINUS_SRC_ALPHA,
+                );
+                try_check!(gl);
+            }
+            gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+            try_check!(gl);
+
+            gl.disable(glow::BLEND);
+            try_check!(gl);
+            // overlays drawn afterwards, e.g. egui, expect to write encoded colors
+            gl.disable(glow::FRAMEBUFFER_SRGB);
+            try_check!(gl);
+            gl.disable(glow::SCISSOR_TEST);
+            try_check!(gl);
+        }
+        Ok(())
+    }
+
+    /// Show the back buffer in the window.
+    ///
+    /// With [`FramebufferBuilder::max_latency`] this waits until the GPU caught up enough.
+    pub fn swap_buffers(&mut self) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        self.surface
+            .as_ref()
+            .ok_or(FramebufferError::Headless)?
             .swap_buffers(&self.ctx_handle)
-            .expect("can swap buffers");
+            .map_err(FramebufferError::SwapBuffers)?;
+        self.limit_latency()
+    }
+
+    /// Show the back buffer, telling the compositor that only the `damage` regions changed.
+    ///
+    /// Regions are `[x, y, width, height]` in window pixels with the origin top-left. Together
+    /// with [`Framebuffer::buffer_age`] this lets widget-style applications redraw and present
+    /// only what changed. Without `EGL_KHR_swap_buffers_with_damage` or
+    /// `EGL_EXT_swap_buffers_with_damage`, and on platforms not using EGL, the whole window is
+    /// swapped.
+    pub fn swap_buffers_with_damage(
+        &mut self,
+        damage: &[[u32; 4]],
+    ) -> Result<(), FramebufferError> {
+        self.make_current_if_needed()?;
+        let surface = self.surface.as_ref().ok_or(FramebufferError::Headless)?;
+        let result = match (surface, &self.ctx_handle) {
+            #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+            (
+                glutin::surface::Surface::Egl(surface),
+                glutin::context::PossiblyCurrentContext::Egl(ctx_handle),
+            ) => {
+                // EGL puts the origin bottom-left
+                let surface_height = self.surface_size[1] as i32;
+                let rects: Vec<_> = damage
+                    .iter()
+                    .map(|&[x, y, width, height]| {
+                        let (width, height) = (width as i32, height as i32);
+                        glutin::surface::Rect::new(
+                            x as i32,
+                            surface_height - y as i32 - height,
+                            width,
+                            height,
+                        )
+                    })
+                    .collect();
+                surface.swap_buffers_with_damage(ctx_handle, &rects)
+            }
+            #[allow(unreachable_patterns)]
+            _ => surface.swap_buffers(&self.ctx_handle),
+        };
+        result.map_err(FramebufferError::SwapBuffers)?;
+        self.limit_latency()
+    }
+
+    /// Age of the back buffer in frames, i.e. how many swaps ago its contents were presented.
+    ///
+    /// An age of 1 means the back buffer holds the last frame, so only what changed since
+    /// needs to be redrawn, 2 the frame before and so on. `None` if the contents are undefined,
+    /// including for new buffers, without `EGL_EXT_buffer_age` and for headless framebuffers,
+    /// in which case everything has to be redrawn.
+    ///
+    /// Note that [`Framebuffer::blit`] always redraws the whole window.
+    pub fn buffer_age(&self) -> Option<u32> {
+        self.surface
+            .as_ref()
+            .map(|surface| surface.buffer_age())
+            .filter(|&age| age > 0)
+    }
+
+    /// Wait for the GPU to finish old frames, see [`FramebufferBuilder::max_latency`].
+    fn limit_latency(&mut self) -> Result<(), FramebufferError> {
+        let Some(max_latency) = self.max_latency else {
+            return Ok(());
+        };
+        let gl = &self.gl;
+        unsafe {
+            let fence = gl
+                .fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .map_err(FramebufferError::ObjectCreation)?;
+            self.latency_fences.push_back(fence);
+            while self.latency_fences.len() > max_latency {
+                let Some(fence) = self.latency_fences.pop_front() else {
+                    break;
+                };
+                while gl.client_wait_sync(fence, glow::SYNC_FLUSH_COMMANDS_BIT, i32::MAX)
+                    == glow::TIMEOUT_EXPIRED
+                {}
+                gl.delete_sync(fence);
+                try_check!(gl);
+            }
+        }
+        Ok(())
+    }
+
+    /// Start copying the last uploaded frame into a dedicated pixel buffer without stalling.
+    ///
+    /// The transfer completes in the background; poll it with
+    /// [`Framebuffer::try_finish_readback`] a frame or two later. Starting a new readback
+    /// invalidates any token still in flight. The framebuffer's context must be current, see
+    /// [`Framebuffer::make_current`]. Fails with [`FramebufferError::ObjectCreation`] if the
+    /// readback buffer or its fence can't be created, and with [`FramebufferError::Gl`] if the
+    /// transfer can't be started.
+    pub fn begin_readback(&mut self) -> Result<ReadbackToken, FramebufferError> {
+        self.debug_assert_current();
+        let gl = &self.gl;
+        let length = self.width * self.height;
+        let readback = match &mut self.readback {
+            Some(readback) => readback,
+            readback @ None => readback.insert(Readback::new(gl, self.texture, length)?),
+        };
+
+        unsafe {
+            if let Some(fence) = readback.fence.take() {
+                gl.delete_sync(fence);
+                try_check!(gl);
+            }
+
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(readback.framebuffer));
+            try_check!(gl);
+            gl.bind_buffer(
+                glow::PIXEL_PACK_BUFFER,
+                Some(readback.pixel_buffer.raw_buffer),
+            );
+            try_check!(gl);
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            try_check!(gl);
+            gl.read_pixels(
+                0,
+                0,
+                self.width as _,
+                self.height as _,
+                Format::GL_FORMAT,
+                Format::GL_TYPE,
+                glow::PixelPackData::BufferOffset(0),
+            );
+            try_check!(gl);
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            try_check!(gl);
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+            try_check!(gl);
+
+            let fence = gl
+                .fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .map_err(FramebufferError::ObjectCreation)?;
+            readback.fence = Some(fence);
+            try_check!(gl);
+            // make sure the fence actually reaches the GPU so it can get signaled
+            gl.flush();
+        }
+
+        self.readback_generation += 1;
+        readback.generation = self.readback_generation;
+        Ok(ReadbackToken {
+            generation: readback.generation,
+        })
+    }
+
+    /// Collect the pixels of a readback if the transfer has completed.
+    ///
+    /// Returns `Ok(None)` while the GPU is still busy and also for tokens that were already
+    /// finished or superseded by a newer [`Framebuffer::begin_readback`]. Rows are in the order
+    /// the painter wrote them in, like for [`Framebuffer::read_pixels`]. The framebuffer's
+    /// context must be current, see [`Framebuffer::make_current`].
+    pub fn try_finish_readback(
+        &mut self,
+        token: &ReadbackToken,
+    ) -> Result<Option<Vec<Format>>, FramebufferError> {
+        self.debug_assert_current();
+        let gl = &self.gl;
+        let Some(readback) = self
+            .readback
+            .as_mut()
+            .filter(|readback| readback.generation == token.generation)
+        else {
+            return Ok(None);
+        };
+        let Some(fence) = readback.fence else {
+            return Ok(None);
+        };
+
+        unsafe {
+            // zero timeout - only poll the fence
+            let status = gl.client_wait_sync(fence, 0, 0);
+            try_check!(gl);
+            if status != glow::ALREADY_SIGNALED && status != glow::CONDITION_SATISFIED {
+                return Ok(None);
+            }
+            gl.delete_sync(fence);
+            try_check!(gl);
+            readback.fence = None;
+
+            let mut pixels = zeroed_pixels::<Format>(readback.pixel_buffer.length);
+            gl.bind_buffer(
+                glow::PIXEL_PACK_BUFFER,
+                Some(readback.pixel_buffer.raw_buffer),
+            );
+            try_check!(gl);
+            gl.get_buffer_sub_data(glow::PIXEL_PACK_BUFFER, 0, pixel_bytes_mut(&mut pixels));
+            let result = check_gl(gl, concat!(file!(), ":", line!()));
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            result?;
+
+            Ok(Some(pixels))
+        }
+    }
+
+    /// Read the last uploaded frame back into client memory, stalling until it is available.
+    ///
+    /// The pixels come from the texture rather than the window, so they exclude the clear color
+    /// and any view transform. Rows are returned in the order the painter wrote them in, i.e.
+    /// starting at the [`Origin`]. See [`Framebuffer::begin_readback`] for a variant that
+    /// doesn't stall.
+    pub fn read_pixels(&self) -> Result<Vec<Format>, FramebufferError> {
+        self.make_current_if_needed()?;
+        let gl = &self.gl;
+        let mut pixels = zeroed_pixels::<Format>(self.width * self.height);
+
+        let result = unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.read_framebuffer));
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            gl.read_pixels(
+                0,
+                0,
+                self.width as _,
+                self.height as _,
+                Format::GL_FORMAT,
+                Format::GL_TYPE,
+                glow::PixelPackData::Slice(pixel_bytes_mut(&mut pixels)),
+            );
+            let result = check_gl(gl, concat!(file!(), ":", line!()));
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+            result
+        };
+        result?;
+        Ok(pixels)
+    }
+
+    /// Save the last uploaded frame as a PNG, oriented the way it is shown in the window.
+    ///
+    /// Formats other than 8 bit RGBA are converted with [`PixelFormat::to_rgba8`]. Needs the
+    /// `png` feature.
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, path: &std::path::Path) -> Result<(), FramebufferError> {
+        let mut pixels = self.read_pixels()?;
+        if self.origin == Origin::BottomLeft {
+            // images are stored top row first
+            util::flip_rows_in_place(&mut pixels, self.width, self.height);
+        }
+        let bytes = pixels.into_iter().flat_map(PixelFormat::to_rgba8).collect();
+        let image = image::RgbaImage::from_raw(self.width as u32, self.height as u32, bytes)
+            .expect("buffer matches the image dimensions");
+        image
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(FramebufferError::Image)
+    }
+}
+
+impl<Format> Framebuffer<Format> {
+    /// Whether the framebuffer renders without a window, see [`Framebuffer::init_headless`].
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Make the framebuffer's context current on this thread, e.g. after using other contexts.
+    ///
+    /// Methods returning a `Result`, like [`Framebuffer::draw`] and [`Framebuffer::resize`], do
+    /// this themselves if another context is current, the others expect the context to be
+    /// current already.
+    pub fn make_current(&self) -> Result<(), FramebufferError> {
+        let result = match &self.surface {
+            Some(surface) => self.ctx_handle.make_current(surface),
+            None => self.make_current_surfaceless(),
+        };
+        result.map_err(FramebufferError::ContextCreation)
+    }
+
+    fn make_current_if_needed(&self) -> Result<(), FramebufferError> {
+        if self.ctx_handle.is_current() {
+            return Ok(());
+        }
+        self.make_current()
+    }
+
+    fn debug_assert_current(&self) {
+        debug_assert!(
+            self.ctx_handle.is_current(),
+            "the framebuffer's context must be current, see `Framebuffer::make_current`"
+        );
+    }
+
+    fn into_current_surfaceless(
+        ctx: glutin::context::NotCurrentContext,
+    ) -> glutin::error::Result<glutin::context::PossiblyCurrentContext> {
+        match ctx {
+            #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+            glutin::context::NotCurrentContext::Egl(ctx) => ctx
+                .make_current_surfaceless()
+                .map(glutin::context::PossiblyCurrentContext::Egl),
+            _ => unreachable!("only headless EGL contexts are created without a surface"),
+        }
+    }
+
+    fn make_current_surfaceless(&self) -> glutin::error::Result<()> {
+        #[cfg(all(any(windows, unix), not(target_vendor = "apple")))]
+        if let glutin::context::PossiblyCurrentContext::Egl(ctx_handle) = &self.ctx_handle {
+            return ctx_handle.make_current_surfaceless();
+        }
+        unreachable!("only headless EGL contexts are created without a surface")
+    }
+}
+
+impl<Format> Drop for Framebuffer<Format> {
+    fn drop(&mut self) {
+        // the GL objects have to go before the fields drop the surface and context
+        if let Err(err) = self.make_current() {
+            log!(
+                error,
+                "failed to make context current, leaking GL objects: {err}"
+            );
+            return;
+        }
+
+        let gl = &self.gl;
+        unsafe {
+            if let Some(readback) = self.readback.take() {
+                readback.delete(gl);
+            }
+            for buffer in &self.pixel_buffers {
+                buffer.delete(gl);
+            }
+            for fence in self.latency_fences.drain(..) {
+                gl.delete_sync(fence);
+            }
+            gl.delete_framebuffer(self.read_framebuffer);
+            gl.delete_texture(self.texture);
+            gl.delete_vertex_array(self.vao);
+            gl.delete_program(self.program);
+            check!(gl);
+        }
+    }
+}
+
+/// Layout of the frame handed to [`Painter::paint_frame`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FrameInfo {
+    /// Width of the frame in pixels, which is also the length of a row.
+    pub width: usize,
+    /// Height of the frame in pixels.
+    pub height: usize,
+    /// Time since the previous frame was painted, zero for the first one.
+    pub dt: Duration,
+    /// Number of frames painted before this one.
+    pub frame: u64,
+    /// Time since the first frame was painted.
+    pub elapsed: Duration,
+}
+
+/// Time spent in each phase of the last frame, see [`Framebuffer::last_metrics`].
+///
+/// Measured on the CPU, so without [`Framebuffer::set_sync_metrics`] the GPU's work may still
+/// be pending when a phase ends and show up in a later one instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// Time the painter took to write the frame, see [`Framebuffer::update`].
+    pub paint: Duration,
+    /// Time spent mapping the pixel buffer and uploading it to the texture.
+    pub upload: Duration,
+    /// Time spent drawing the texture and swapping buffers, see [`Framebuffer::present`].
+    pub present: Duration,
+}
+
+/// Source of the timing in [`FrameInfo`].
+#[derive(Default)]
+struct FrameClock {
+    // time of the first and the latest painted frame
+    times: Option<(Instant, Instant)>,
+    frame: u64,
+}
+
+impl FrameClock {
+    /// Describe the next frame of the given size.
+    fn tick(&mut self, width: usize, height: usize) -> FrameInfo {
+        let now = Instant::now();
+        let (start, last) = self.times.unwrap_or((now, now));
+        self.times = Some((start, now));
+        let frame = FrameInfo {
+            width,
+            height,
+            dt: now - last,
+            frame: self.frame,
+            elapsed: now - start,
+        };
+        self.frame += 1;
+        frame
     }
 }
 
+impl FrameInfo {
+    /// Wrap the pixels of this frame in a [`PixelView`] for 2D access.
+    pub fn view<'a, F>(&self, pixels: &'a mut [F]) -> PixelView<'a, F> {
+        PixelView::new(pixels, self.width)
+    }
+}
+
+/// 2D view over a row-major pixel slice.
+pub struct PixelView<'a, F> {
+    pixels: &'a mut [F],
+    width: usize,
+}
+
+impl<'a, F> PixelView<'a, F> {
+    /// Panics if `width` is zero or the length of `pixels` is not a multiple of it.
+    pub fn new(pixels: &'a mut [F], width: usize) -> Self {
+        assert!(
+            width > 0 && pixels.len().is_multiple_of(width),
+            "{} pixels can't be split into rows of {width}",
+            pixels.len()
+        );
+        Self { pixels, width }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.pixels.len() / self.width
+    }
+
+    /// Pixel at column `x` of row `y`, or `None` if it lies outside of the view.
+    pub fn get(&self, x: usize, y: usize) -> Option<&F> {
+        self.index(x, y).map(|index| &self.pixels[index])
+    }
+
+    /// Pixel at column `x` of row `y`, or `None` if it lies outside of the view.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut F> {
+        self.index(x, y).map(|index| &mut self.pixels[index])
+    }
+
+    /// Iterate over the rows from top to bottom.
+    pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, F> {
+        self.pixels.chunks_exact_mut(self.width)
+    }
+
+    /// Access the underlying slice.
+    pub fn as_mut_slice(&mut self) -> &mut [F] {
+        self.pixels
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height()).then(|| y * self.width + x)
+    }
+}
+
+impl<F: Copy> PixelView<'_, F> {
+    /// Fill the `width` x `height` rectangle whose top left corner is at (`x`, `y`).
+    ///
+    /// Panics if the rectangle doesn't fit in the view.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, pixel: F) {
+        assert!(
+            x + width <= self.width && y + height <= self.height(),
+            "rectangle {width}x{height} at ({x}, {y}) is outside of the {}x{} view",
+            self.width,
+            self.height()
+        );
+        for row in self.rows_mut().skip(y).take(height) {
+            row[x..x + width].fill(pixel);
+        }
+    }
+
+    /// Fill the whole view with `pixel`.
+    pub fn fill(&mut self, pixel: F) {
+        self.pixels.fill(pixel);
+    }
+
+    /// Draw `text` in `color` with its top left corner at (`x`, `y`), see
+    /// [`text::draw_text`]. Needs the `text` feature.
+    #[cfg(feature = "text")]
+    pub fn draw_text(&mut self, text: &str, x: usize, y: usize, color: F) {
+        text::draw_text(self.pixels, self.width, text, x, y, color);
+    }
+}
+
+/// Create a channel for handing frames painted on a worker thread to the thread drawing them.
+///
+/// Frames are double buffered: the receiver hands buffers it is done with back to the sender,
+/// so a steady stream of frames doesn't allocate.
+pub fn frame_channel<F>() -> (FrameSender<F>, FrameReceiver<F>) {
+    let (frames, received) = mpsc::channel();
+    let (recycle, recycled) = mpsc::channel();
+    (
+        FrameSender { frames, recycled },
+        FrameReceiver { received, recycle },
+    )
+}
+
+/// Sending half of a [`frame_channel`], can be moved to another thread.
+pub struct FrameSender<F> {
+    frames: mpsc::Sender<Vec<F>>,
+    recycled: mpsc::Receiver<Vec<F>>,
+}
+
+impl<F: Clone> FrameSender<F> {
+    /// Buffer of `length` pixels to paint the next frame into.
+    ///
+    /// Reuses a buffer handed back by the receiver if there is one, its contents are those of an
+    /// old frame. Otherwise a new buffer is filled with `fill`.
+    pub fn buffer(&self, length: usize, fill: F) -> Vec<F> {
+        match self.recycled.try_recv() {
+            Ok(mut buffer) => {
+                buffer.resize(length, fill);
+                buffer
+            }
+            Err(_) => vec![fill; length],
+        }
+    }
+}
+
+impl<F> FrameSender<F> {
+    /// Send a finished frame, giving it back if the receiver is gone.
+    pub fn send(&self, frame: Vec<F>) -> Result<(), Vec<F>> {
+        self.frames.send(frame).map_err(|err| err.0)
+    }
+}
+
+/// Receiving half of a [`frame_channel`].
+pub struct FrameReceiver<F> {
+    received: mpsc::Receiver<Vec<F>>,
+    recycle: mpsc::Sender<Vec<F>>,
+}
+
+impl<F> FrameReceiver<F> {
+    /// The most recent frame sent, if any arrived since the last call.
+    ///
+    /// Older frames are skipped and handed back to the sender.
+    pub fn latest(&self) -> Option<Vec<F>> {
+        let mut latest = None;
+        while let Ok(frame) = self.received.try_recv() {
+            if let Some(skipped) = latest.replace(frame) {
+                self.recycle(skipped);
+            }
+        }
+        latest
+    }
+
+    /// Hand a frame back to the sender for reuse once it has been presented.
+    pub fn recycle(&self, frame: Vec<F>) {
+        // the sender may already be gone, the buffer is simply dropped then
+        let _ = self.recycle.send(frame);
+    }
+}
+
+/// What a [`Painter`] wants to happen after a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaintOutcome {
+    /// Keep going, redrawing for as long as [`Painter::is_dirty`] says so.
+    Continue,
+    /// Redraw no earlier than `when`, sleeping until then.
+    Redraw { when: Instant },
+    /// Painting is done and the application should quit.
+    Stop,
+    /// Nothing was painted, as the framebuffer isn't [visible](Framebuffer::is_visible).
+    ///
+    /// Returned by [`Framebuffer::draw`] rather than by painters. Event loops should wait
+    /// without requesting redraws until the window is shown again, i.e. for
+    /// `WindowEvent::Occluded(false)` or a non-zero `WindowEvent::Resized`.
+    Hidden,
+}
+
 pub trait Painter {
     type Pixel;
 
-    fn paint(&mut self, pixels: &mut [Self::Pixel]);
+    /// Write the next frame into `pixels`.
+    ///
+    /// `pixels` is mapped write-only and its contents are undefined, so every pixel has to be
+    /// written, or at least those inside of [`Painter::dirty_rect`]. Painters which need the frame
+    /// dimensions implement [`Painter::paint_frame`] instead.
+    fn paint(&mut self, _pixels: &mut [Self::Pixel]) {}
+
+    /// Write the next frame into `pixels`, laid out row by row as described by `frame`.
+    ///
+    /// The returned [`PaintOutcome`] tells the event loop when the next frame is wanted.
+    ///
+    /// Forwards to [`Painter::paint`] by default, so painters written against the old signature
+    /// keep working.
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        let _ = frame;
+        self.paint(pixels);
+        PaintOutcome::Continue
+    }
+
+    /// Region `[x, y, width, height]` (in pixels) changed by the last paint.
+    ///
+    /// Only this region is uploaded and pixels outside of it need not be written. Rows count
+    /// from the [`Origin`], like those of [`FrameInfo::view`]. The texture starts out undefined,
+    /// also after a resize, so the first frame of each size should cover everything. The
+    /// default of `None` uploads the whole frame.
+    fn dirty_rect(&self) -> Option<[usize; 4]> {
+        None
+    }
+
+    /// Whether the next frame differs from the last one painted.
+    ///
+    /// Event loops can use this to sleep instead of redrawing identical frames.
+    fn is_dirty(&self) -> bool {
+        true
+    }
+}
+
+/// [`Painter`] which calls a closure with the pixels, created with [`fn_painter`].
+pub struct FnPainter<P, F> {
+    paint: F,
+    pixel: PhantomData<P>,
+}
+
+impl<P, F: FnMut(&mut [P])> Painter for FnPainter<P, F> {
+    type Pixel = P;
+
+    fn paint(&mut self, pixels: &mut [Self::Pixel]) {
+        (self.paint)(pixels)
+    }
+}
+
+/// Wrap a closure into a [`Painter`] for one-off drawing.
+///
+/// ```no_run
+/// # fn draw(framebuffer: &mut cpf::Framebuffer<[u8; 4]>) -> Result<(), cpf::FramebufferError> {
+/// framebuffer.draw(&mut cpf::fn_painter(|pixels| pixels.fill([255, 0, 0, 255])))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn fn_painter<P, F: FnMut(&mut [P])>(paint: F) -> FnPainter<P, F> {
+    FnPainter {
+        paint,
+        pixel: PhantomData,
+    }
+}
+
+/// [`Painter`] computing each pixel in parallel, created with [`par_painter`].
+#[cfg(feature = "rayon")]
+pub struct ParPainter<P, F> {
+    paint: F,
+    pixel: PhantomData<P>,
+}
+
+#[cfg(feature = "rayon")]
+impl<P: Send, F: Fn(usize, usize, &mut P) + Sync> Painter for ParPainter<P, F> {
+    type Pixel = P;
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        util::par_fill(pixels, frame.width, &self.paint);
+        PaintOutcome::Continue
+    }
+}
+
+/// Wrap a closure setting the pixel at `(x, y)` into a [`Painter`] which runs it on all cores,
+/// see [`util::par_fill`]. Needs the `rayon` feature.
+///
+/// ```no_run
+/// # fn draw(framebuffer: &mut cpf::Framebuffer<[u8; 4]>) -> Result<(), cpf::FramebufferError> {
+/// framebuffer.draw(&mut cpf::par_painter(|x, y, pixel: &mut [u8; 4]| {
+///     *pixel = [(x ^ y) as u8, 0, 0, 255];
+/// }))?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_painter<P: Send, F: Fn(usize, usize, &mut P) + Sync>(paint: F) -> ParPainter<P, F> {
+    ParPainter {
+        paint,
+        pixel: PhantomData,
+    }
+}
+
+/// Reference image for checking that a framebuffer works and is oriented correctly.
+///
+/// A gray checkerboard of `cell_size` pixel squares with a black to white gradient through the
+/// middle, and a red, green, blue and white square in the top left, top right, bottom left and
+/// bottom right corner. With the `text` feature the frame size is written next to the red
+/// square, unless turned off with [`TestPattern::without_label`]. Works with every
+/// [`PixelFormat`], through [`PixelFormat::from_rgba8`].
+///
+/// ```no_run
+/// # fn draw(framebuffer: &mut cpf::Framebuffer<[u8; 4]>) -> Result<(), cpf::FramebufferError> {
+/// framebuffer.draw(&mut cpf::TestPattern::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestPattern<F> {
+    cell_size: usize,
+    label: bool,
+    painted: bool,
+    pixel: PhantomData<F>,
+}
+
+impl<F> Default for TestPattern<F> {
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+impl<F> TestPattern<F> {
+    /// Pattern with checkerboard cells and corner squares of `cell_size` pixels.
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is 0.
+    pub fn new(cell_size: usize) -> Self {
+        assert!(cell_size > 0, "the cells need a size");
+        Self {
+            cell_size,
+            label: true,
+            painted: false,
+            pixel: PhantomData,
+        }
+    }
+
+    /// Leave out the frame size label, so every pixel matches [`TestPattern::pixel`].
+    pub fn without_label(mut self) -> Self {
+        self.label = false;
+        self
+    }
+}
+
+impl<F: PixelFormat> TestPattern<F> {
+    /// Pixel at `(x, y)` of a `width` by `height` frame, e.g. to compare a readback against.
+    ///
+    /// The frame size label isn't included, see [`TestPattern::without_label`].
+    pub fn pixel(&self, x: usize, y: usize, width: usize, height: usize) -> F {
+        let cell = self.cell_size;
+        let (left, top) = (x < cell, y < cell);
+        let (right, bottom) = (x + cell >= width, y + cell >= height);
+        let band = height / 2;
+        let rgba = match (left, top, right, bottom) {
+            (true, true, _, _) => [255, 0, 0, 255],
+            (_, true, true, _) => [0, 255, 0, 255],
+            (true, _, _, true) => [0, 0, 255, 255],
+            (_, _, true, true) => [255, 255, 255, 255],
+            _ if y + cell / 2 >= band && y < band + cell.div_ceil(2) => {
+                let value = (x * 255 / width.saturating_sub(1).max(1)) as u8;
+                [value, value, value, 255]
+            }
+            _ if (x / cell + y / cell).is_multiple_of(2) => [64, 64, 64, 255],
+            _ => [192, 192, 192, 255],
+        };
+        F::from_rgba8(rgba)
+    }
+}
+
+impl<F: PixelFormat> Painter for TestPattern<F> {
+    type Pixel = F;
+
+    fn paint_frame(&mut self, pixels: &mut [Self::Pixel], frame: &FrameInfo) -> PaintOutcome {
+        for (y, row) in frame.view(pixels).rows_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.pixel(x, y, frame.width, frame.height);
+            }
+        }
+        #[cfg(feature = "text")]
+        if self.label {
+            let label = format!("{}x{}", frame.width, frame.height);
+            let margin = self.cell_size / 4;
+            let (x, y) = (self.cell_size + margin, margin);
+            let (width, height) = text::text_size(&label);
+            let mut view = frame.view(pixels);
+            if x + width <= view.width() && y + height <= view.height() {
+                view.fill_rect(x, y, width, height, F::from_rgba8([0, 0, 0, 255]));
+                view.draw_text(&label, x, y, F::from_rgba8([255, 255, 0, 255]));
+            }
+        }
+        self.painted = true;
+        PaintOutcome::Continue
+    }
+
+    fn is_dirty(&self) -> bool {
+        !self.painted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(transparency: bool, samples: u8, color_bits: u8, alpha_bits: u8) -> ConfigInfo {
+        ConfigInfo {
+            color_bits: [color_bits; 3],
+            alpha_bits,
+            depth_bits: 24,
+            stencil_bits: 8,
+            samples,
+            transparency,
+            srgb: false,
+            hardware_accelerated: true,
+        }
+    }
+
+    /// Best of `configs` for `builder`, checked to be the same for the reversed order.
+    fn pick(configs: &[ConfigInfo], builder: &FramebufferBuilder<[u8; 4]>) -> ConfigInfo {
+        let score = |config: &&ConfigInfo| config.score(builder);
+        let forward = *best_by_score(configs, score).unwrap();
+        let backward = *best_by_score(configs.iter().rev(), score).unwrap();
+        assert_eq!(
+            forward, backward,
+            "the pick depends on the order of the configs"
+        );
+        forward
+    }
+
+    #[test]
+    fn transparency_beats_samples_when_requested() {
+        let transparent = config(true, 0, 8, 8);
+        let opaque = config(false, 4, 8, 8);
+        let builder = FramebufferBuilder::new().transparent(true).samples(4);
+        assert_eq!(pick(&[transparent, opaque], &builder), transparent);
+        assert_eq!(pick(&[opaque, transparent], &builder), transparent);
+    }
+
+    #[test]
+    fn samples_closest_to_the_request_win() {
+        let configs = [
+            config(true, 0, 8, 8),
+            config(false, 4, 8, 8),
+            config(false, 8, 8, 8),
+        ];
+        let builder = FramebufferBuilder::new().samples(4);
+        assert_eq!(pick(&configs, &builder), configs[1]);
+        // transparency counts only when requested, and no request means the fewest samples
+        assert_eq!(pick(&configs, &FramebufferBuilder::new()), configs[0]);
+    }
+
+    #[test]
+    fn color_bits_then_alpha_bits_break_ties() {
+        let configs = [
+            config(false, 0, 8, 0),
+            config(false, 0, 10, 2),
+            config(false, 0, 8, 8),
+        ];
+        assert_eq!(pick(&configs, &FramebufferBuilder::new()), configs[1]);
+        assert_eq!(
+            pick(&[configs[0], configs[2]], &FramebufferBuilder::new()),
+            configs[2]
+        );
+    }
+
+    #[test]
+    fn ties_keep_the_first_config() {
+        let mut first = config(false, 0, 8, 8);
+        let mut second = first;
+        first.depth_bits = 16;
+        second.depth_bits = 32;
+        let picked = best_by_score([first, second], |config| {
+            config.score(&FramebufferBuilder::<[u8; 4]>::new())
+        });
+        assert_eq!(picked, Some(first));
+    }
+
+    /// Internal formats the pixel formats use by default, also with sRGB.
+    fn default_internal_formats<Format: PixelFormat>() -> [u32; 2] {
+        let builder = FramebufferBuilder::<Format>::new();
+        [false, true].map(|srgb| builder.texture_internal_format(srgb).unwrap())
+    }
+
+    #[test]
+    fn default_internal_formats_are_accepted_as_overrides() {
+        let defaults = [
+            default_internal_formats::<[u8; 4]>(),
+            default_internal_formats::<[u8; 3]>(),
+            default_internal_formats::<[f32; 4]>(),
+            default_internal_formats::<[u16; 4]>(),
+            default_internal_formats::<u8>(),
+            default_internal_formats::<Bgra8>(),
+        ];
+        for format in defaults.into_iter().flatten() {
+            let builder = FramebufferBuilder::<[u8; 4]>::new().internal_format(format);
+            assert_eq!(builder.texture_internal_format(false).unwrap(), format);
+        }
+    }
+
+    #[test]
+    fn srgb_rgb_pixels_use_srgb8() {
+        assert_eq!(
+            default_internal_formats::<[u8; 3]>(),
+            [glow::RGB8, glow::SRGB8]
+        );
+        let builder = FramebufferBuilder::<[u8; 3]>::new().internal_format(glow::SRGB8);
+        assert_eq!(builder.texture_internal_format(false).unwrap(), glow::SRGB8);
+    }
+
+    #[test]
+    fn unsupported_internal_formats_are_rejected() {
+        let builder = FramebufferBuilder::<[u8; 4]>::new().internal_format(glow::RGBA8UI);
+        assert!(matches!(
+            builder.texture_internal_format(false),
+            Err(FramebufferError::UnsupportedInternalFormat(glow::RGBA8UI))
+        ));
+    }
 }
@@ -0,0 +1,167 @@
+//! Ready-made event loop for applications which only paint, see [`run_painter`].
+use winit::application::ApplicationHandler;
+use winit::event::{StartCause, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::window::{Window, WindowId};
+
+use crate::util::FrameLimiter;
+use crate::{Framebuffer, FramebufferError, PaintOutcome, Painter, PixelFormat};
+
+/// Open a `width` by `height` window and show the frames of `painter` until it is closed.
+///
+/// Owns the event loop and blocks until the window is closed or the painter returns
+/// [`PaintOutcome::Stop`]. Frames are drawn as requested by the [`PaintOutcome`]s, the
+/// framebuffer follows the window size, also across DPI changes, and is recreated if the context
+/// is lost. Applications which handle input or draw more than one window drive a
+/// [`Framebuffer`] from their own event loop instead.
+///
+/// Painters which always want a new frame are redrawn as fast as possible, or as vsync allows,
+/// see [`run_painter_limited`] to cap the frame rate.
+///
+/// ```no_run
+/// fn main() -> Result<(), cpf::FramebufferError> {
+///     cpf::run_painter(320, 240, cpf::fn_painter(|pixels| pixels.fill([255u8, 0, 0, 255])))
+/// }
+/// ```
+pub fn run_painter<P: Painter<Pixel: PixelFormat>>(
+    width: usize,
+    height: usize,
+    painter: P,
+) -> Result<(), FramebufferError> {
+    run(App {
+        size: (width, height),
+        painter,
+        limiter: None,
+        surface: None,
+        error: None,
+    })
+}
+
+/// Like [`run_painter`], but drawing at most `max_fps` frames per second.
+///
+/// Between frames the event loop sleeps, see [`FrameLimiter`]. The time spent drawing counts
+/// towards the frame interval, so the cap holds as long as frames are drawn faster than it.
+///
+/// # Panics
+///
+/// If `max_fps` is not a positive, finite number.
+pub fn run_painter_limited<P: Painter<Pixel: PixelFormat>>(
+    width: usize,
+    height: usize,
+    max_fps: f64,
+    painter: P,
+) -> Result<(), FramebufferError> {
+    run(App {
+        size: (width, height),
+        painter,
+        limiter: Some(FrameLimiter::new(max_fps)),
+        surface: None,
+        error: None,
+    })
+}
+
+fn run<P: Painter<Pixel: PixelFormat>>(mut app: App<P>) -> Result<(), FramebufferError> {
+    let event_loop = EventLoop::new().map_err(FramebufferError::EventLoop)?;
+    event_loop
+        .run_app(&mut app)
+        .map_err(FramebufferError::EventLoop)?;
+    app.error.map_or(Ok(()), Err)
+}
+
+struct App<P: Painter> {
+    size: (usize, usize),
+    painter: P,
+    limiter: Option<FrameLimiter>,
+    surface: Option<(Window, Framebuffer<P::Pixel>)>,
+    // ends the event loop, returned by `run_painter`
+    error: Option<FramebufferError>,
+}
+
+impl<P: Painter<Pixel: PixelFormat>> App<P> {
+    fn fail(&mut self, event_loop: &ActiveEventLoop, err: FramebufferError) {
+        self.error = Some(err);
+        event_loop.exit();
+    }
+}
+
+impl<P: Painter<Pixel: PixelFormat>> ApplicationHandler for App<P> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.surface.is_none() {
+            let (width, height) = self.size;
+            match Framebuffer::init(event_loop, width, height) {
+                Ok(surface) => self.surface = Some(surface),
+                Err(err) => self.fail(event_loop, err),
+            }
+        }
+    }
+
+    fn new_events(&mut self, _: &ActiveEventLoop, cause: StartCause) {
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some((window, _)) = self.surface.as_ref() {
+                window.request_redraw();
+            }
+        }
+    }
+
+    fn exiting(&mut self, _: &ActiveEventLoop) {
+        // release the context and window while the event loop is still alive
+        self.surface = None;
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some((window, framebuffer)) = self.surface.as_mut() else {
+            return;
+        };
+        match event {
+            WindowEvent::RedrawRequested => match framebuffer.draw(&mut self.painter) {
+                Ok(PaintOutcome::Continue) if self.painter.is_dirty() => match &mut self.limiter {
+                    // the redraw is requested once the timer fires, see `new_events`
+                    Some(limiter) => {
+                        event_loop.set_control_flow(ControlFlow::WaitUntil(limiter.next_frame()))
+                    }
+                    None => {
+                        event_loop.set_control_flow(ControlFlow::Poll);
+                        window.request_redraw();
+                    }
+                },
+                Ok(PaintOutcome::Continue) => event_loop.set_control_flow(ControlFlow::Wait),
+                Ok(PaintOutcome::Redraw { when }) => {
+                    let earliest = self.limiter.as_mut().map(FrameLimiter::next_frame);
+                    let when = earliest.map_or(when, |earliest| earliest.max(when));
+                    event_loop.set_control_flow(ControlFlow::WaitUntil(when))
+                }
+                Ok(PaintOutcome::Stop) => event_loop.exit(),
+                // redrawn once shown again
+                Ok(PaintOutcome::Hidden) => event_loop.set_control_flow(ControlFlow::Wait),
+                Err(FramebufferError::ContextLost) => match framebuffer.reinit() {
+                    Ok(()) => window.request_redraw(),
+                    Err(err) => self.fail(event_loop, err),
+                },
+                Err(err) => self.fail(event_loop, err),
+            },
+            WindowEvent::Resized(size) => {
+                // zero while minimized, drawing resumes with the next non-zero size
+                match framebuffer.resize(size.width as usize, size.height as usize) {
+                    Ok(()) if framebuffer.is_visible() => window.request_redraw(),
+                    Ok(()) => {}
+                    Err(err) => self.fail(event_loop, err),
+                }
+            }
+            // the framebuffer follows the size of the `Resized` event which comes after
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                framebuffer.handle_scale_change(scale_factor, window.inner_size());
+                if framebuffer.is_visible() {
+                    window.request_redraw();
+                }
+            }
+            WindowEvent::Occluded(occluded) => {
+                framebuffer.set_occluded(occluded);
+                if framebuffer.is_visible() {
+                    window.request_redraw();
+                }
+            }
+            WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
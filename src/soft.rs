@@ -0,0 +1,166 @@
+//! CPU presentation through [softbuffer](https://github.com/rust-windowing/softbuffer), for
+//! machines without working OpenGL.
+//!
+//! [`SoftwareFramebuffer`] runs the same [`Painter`]s as [`Framebuffer`], and
+//! [`AutoFramebuffer::init`] picks whichever of the two can be created.
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use winit::dpi::PhysicalSize;
+use winit::{event_loop::ActiveEventLoop, window::Window};
+
+use crate::{FrameClock, Framebuffer, FramebufferError, PaintOutcome, Painter};
+
+fn software_error(err: softbuffer::SoftBufferError) -> FramebufferError {
+    FramebufferError::Software(err.to_string())
+}
+
+/// Framebuffer which converts the pixels on the CPU and blits them to the window.
+pub struct SoftwareFramebuffer {
+    surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+    pixels: Vec<[u8; 4]>,
+    width: usize,
+    height: usize,
+    clock: FrameClock,
+}
+
+impl SoftwareFramebuffer {
+    pub fn init(
+        event_loop: &ActiveEventLoop,
+        width: usize,
+        height: usize,
+    ) -> Result<(Arc<Window>, Self), FramebufferError> {
+        let window_attributes = Window::default_attributes().with_inner_size(PhysicalSize {
+            width: width as u32,
+            height: height as u32,
+        });
+        let window = Arc::new(
+            event_loop
+                .create_window(window_attributes)
+                .map_err(|err| FramebufferError::SurfaceBuild(err.to_string()))?,
+        );
+        let context = softbuffer::Context::new(window.clone()).map_err(software_error)?;
+        let surface = softbuffer::Surface::new(&context, window.clone()).map_err(software_error)?;
+
+        let mut framebuffer = Self {
+            surface,
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+            clock: FrameClock::default(),
+        };
+        framebuffer.resize(width, height)?;
+        Ok((window, framebuffer))
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Reallocate the framebuffer and the window surface for a new size.
+    ///
+    /// Zero sizes, as reported for minimized windows, are ignored.
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), FramebufferError> {
+        let (Some(surface_width), Some(surface_height)) = (
+            NonZeroU32::new(width as u32),
+            NonZeroU32::new(height as u32),
+        ) else {
+            return Ok(());
+        };
+        self.surface
+            .resize(surface_width, surface_height)
+            .map_err(software_error)?;
+        self.pixels = vec![[0; 4]; width * height];
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Paint and present a frame, like [`Framebuffer::draw`].
+    pub fn draw(
+        &mut self,
+        painter: &mut impl Painter<Pixel = [u8; 4]>,
+    ) -> Result<PaintOutcome, FramebufferError> {
+        let outcome = self.update(painter)?;
+        self.present()?;
+        Ok(outcome)
+    }
+
+    /// Let the painter write the next frame.
+    pub fn update(
+        &mut self,
+        painter: &mut impl Painter<Pixel = [u8; 4]>,
+    ) -> Result<PaintOutcome, FramebufferError> {
+        let frame = self.clock.tick(self.width, self.height);
+        Ok(painter.paint_frame(&mut self.pixels, &frame))
+    }
+
+    /// Convert the pixels to the window's format and show them.
+    pub fn present(&mut self) -> Result<(), FramebufferError> {
+        let mut buffer = self.surface.buffer_mut().map_err(software_error)?;
+        // softbuffer expects 0RGB
+        for (target, &[r, g, b, _]) in buffer.iter_mut().zip(&self.pixels) {
+            *target = u32::from_be_bytes([0, r, g, b]);
+        }
+        buffer.present().map_err(software_error)
+    }
+}
+
+/// OpenGL framebuffer, or the software fallback if OpenGL is not available.
+pub enum AutoFramebuffer {
+    Gl(Box<Framebuffer<[u8; 4]>>),
+    Software(SoftwareFramebuffer),
+}
+
+impl AutoFramebuffer {
+    /// Create an OpenGL framebuffer, falling back to software rendering if that fails.
+    ///
+    /// The OpenGL error is logged before falling back.
+    pub fn init(
+        event_loop: &ActiveEventLoop,
+        width: usize,
+        height: usize,
+    ) -> Result<(Arc<Window>, Self), FramebufferError> {
+        match Framebuffer::init(event_loop, width, height) {
+            Ok((window, framebuffer)) => {
+                Ok((Arc::new(window), AutoFramebuffer::Gl(Box::new(framebuffer))))
+            }
+            Err(err) => {
+                log!(
+                    warn,
+                    "failed to initialize OpenGL, falling back to software: {err}"
+                );
+                let (window, framebuffer) = SoftwareFramebuffer::init(event_loop, width, height)?;
+                Ok((window, AutoFramebuffer::Software(framebuffer)))
+            }
+        }
+    }
+
+    pub fn is_software(&self) -> bool {
+        matches!(self, AutoFramebuffer::Software(_))
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        match self {
+            AutoFramebuffer::Gl(framebuffer) => framebuffer.dimensions(),
+            AutoFramebuffer::Software(framebuffer) => framebuffer.dimensions(),
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), FramebufferError> {
+        match self {
+            AutoFramebuffer::Gl(framebuffer) => framebuffer.resize(width, height),
+            AutoFramebuffer::Software(framebuffer) => framebuffer.resize(width, height),
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        painter: &mut impl Painter<Pixel = [u8; 4]>,
+    ) -> Result<PaintOutcome, FramebufferError> {
+        match self {
+            AutoFramebuffer::Gl(framebuffer) => framebuffer.draw(painter),
+            AutoFramebuffer::Software(framebuffer) => framebuffer.draw(painter),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Immediate mode UI drawn over the framebuffer with [egui](https://github.com/emilk/egui).
+//!
+//! The overlay shares the framebuffer's OpenGL context and is painted between
+//! [`Framebuffer::blit`] and [`Framebuffer::swap_buffers`]:
+//!
+//! ```no_run
+//! # fn frame(
+//! #     framebuffer: &mut cpf::Framebuffer<[u8; 4]>,
+//! #     overlay: &mut cpf::ui::EguiOverlay,
+//! #     window: &winit::window::Window,
+//! #     painter: &mut impl cpf::Painter<Pixel = [u8; 4]>,
+//! # ) -> Result<(), cpf::FramebufferError> {
+//! framebuffer.update(painter)?;
+//! framebuffer.blit()?;
+//! overlay.run(window, framebuffer, |ctx| {
+//!     cpf::ui::egui::Window::new("stats").show(ctx, |ui| ui.label("hello"));
+//! });
+//! framebuffer.swap_buffers()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{Framebuffer, PixelFormat};
+use egui_glow::EguiGlow;
+use glow::HasContext;
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+pub use egui_glow::egui_winit::{egui, EventResponse};
+
+/// egui state and renderer living in a framebuffer's OpenGL context.
+///
+/// The overlay has to be dropped while the context is still alive and current, i.e. before the
+/// framebuffer.
+pub struct EguiOverlay {
+    egui_glow: EguiGlow,
+}
+
+impl EguiOverlay {
+    pub fn new<Format: PixelFormat>(
+        event_loop: &ActiveEventLoop,
+        framebuffer: &Framebuffer<Format>,
+    ) -> Self {
+        Self {
+            egui_glow: EguiGlow::new(event_loop, framebuffer.gl.clone(), None, None, true),
+        }
+    }
+
+    pub fn context(&self) -> &egui::Context {
+        &self.egui_glow.egui_ctx
+    }
+
+    /// Pass a window event on to egui.
+    ///
+    /// The response tells whether egui consumed the event and wants to be repainted.
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
+        self.egui_glow.on_window_event(window, event)
+    }
+
+    /// Build the UI with `run_ui` and paint it over what was drawn to the back buffer so far.
+    pub fn run<Format: PixelFormat>(
+        &mut self,
+        window: &Window,
+        framebuffer: &Framebuffer<Format>,
+        run_ui: impl FnMut(&egui::Context),
+    ) {
+        self.egui_glow.run(window, run_ui);
+        self.egui_glow.paint(window);
+
+        // egui leaves blending and the scissor test enabled, which would affect the next blit
+        let gl = framebuffer.gl();
+        unsafe {
+            gl.disable(glow::BLEND);
+            check!(gl);
+            gl.disable(glow::SCISSOR_TEST);
+            check!(gl);
+        }
+    }
+}
+
+impl Drop for EguiOverlay {
+    fn drop(&mut self) {
+        self.egui_glow.destroy();
+    }
+}
@@ -0,0 +1,189 @@
+//! Helpers for working with pixel data and frame timing outside of a
+//! [`Framebuffer`](crate::Framebuffer).
+
+use std::time::{Duration, Instant};
+
+use crate::PixelFormat;
+
+/// Reverse the order of the rows of a `width` by `height` image, turning it upside down.
+///
+/// Converts between OpenGL's bottom-left origin and the top-left origin of most image sources.
+///
+/// ```
+/// let mut pixels = [1, 2, 3, 4, 5, 6];
+/// cpf::util::flip_rows_in_place(&mut pixels, 2, 3);
+/// assert_eq!(pixels, [5, 6, 3, 4, 1, 2]);
+/// ```
+///
+/// # Panics
+///
+/// If `pixels` doesn't hold exactly `width * height` pixels.
+pub fn flip_rows_in_place<F>(pixels: &mut [F], width: usize, height: usize) {
+    assert_eq!(
+        pixels.len(),
+        width * height,
+        "{} pixels don't form a {width}x{height} image",
+        pixels.len()
+    );
+    if width == 0 {
+        return;
+    }
+    // swap rows pairwise from the outside in, the middle row of odd heights stays
+    let (top, bottom) = pixels.split_at_mut(height / 2 * width);
+    let bottom = &mut bottom[height % 2 * width..];
+    for (upper, lower) in top
+        .chunks_exact_mut(width)
+        .zip(bottom.chunks_exact_mut(width).rev())
+    {
+        upper.swap_with_slice(lower);
+    }
+}
+
+/// Copy of a `width` by `height` image with the order of its rows reversed, see
+/// [`flip_rows_in_place`].
+///
+/// # Panics
+///
+/// If `pixels` doesn't hold exactly `width * height` pixels.
+pub fn flip_rows<F: Clone>(pixels: &[F], width: usize, height: usize) -> Vec<F> {
+    assert_eq!(
+        pixels.len(),
+        width * height,
+        "{} pixels don't form a {width}x{height} image",
+        pixels.len()
+    );
+    if width == 0 {
+        return Vec::new();
+    }
+    pixels
+        .chunks_exact(width)
+        .rev()
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Convert each pixel of `src` to the format of `dst`, by way of 8 bit RGBA.
+///
+/// ```
+/// let rgb: [[u8; 3]; 2] = [[255, 0, 0], [0, 0, 255]];
+/// let mut rgba = [[0u8; 4]; 2];
+/// cpf::util::convert_into(&rgb, &mut rgba);
+/// assert_eq!(rgba, [[255, 0, 0, 255], [0, 0, 255, 255]]);
+/// ```
+///
+/// # Panics
+///
+/// If `src` and `dst` hold a different number of pixels.
+pub fn convert_into<Src: PixelFormat, Dst: PixelFormat>(src: &[Src], dst: &mut [Dst]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "converting {} pixels into {}",
+        src.len(),
+        dst.len()
+    );
+    for (target, &pixel) in dst.iter_mut().zip(src) {
+        *target = Dst::from_rgba8(pixel.to_rgba8());
+    }
+}
+
+/// Set every pixel of a `width` pixels wide image to `paint(x, y, pixel)`, with the rows spread
+/// over rayon's thread pool. Needs the `rayon` feature.
+///
+/// Speeds up painters which compute each pixel independently, e.g. from a formula, see also
+/// [`par_painter`](crate::par_painter).
+///
+/// ```
+/// let mut pixels = vec![[0u8; 4]; 4 * 3];
+/// cpf::util::par_fill(&mut pixels, 4, |x, y, pixel| *pixel = [x as u8, y as u8, 0, 255]);
+/// assert_eq!(pixels[4 + 3], [3, 1, 0, 255]);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_fill<F: Send>(
+    pixels: &mut [F],
+    width: usize,
+    paint: impl Fn(usize, usize, &mut F) + Sync,
+) {
+    use rayon::prelude::*;
+
+    if width == 0 {
+        return;
+    }
+    pixels
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                paint(x, y, pixel);
+            }
+        });
+}
+
+/// Caps the frame rate of a loop, for when vsync is off or the display is faster than needed.
+///
+/// Call [`FrameLimiter::next_frame`] after each frame and wait until the returned deadline, e.g.
+/// with `ControlFlow::WaitUntil`, or let [`FrameLimiter::wait`] sleep. Deadlines follow each
+/// other by the interval regardless of how long a frame took to draw, so the time spent drawing
+/// counts towards the interval. A loop which falls behind draws the next frame right away
+/// instead of catching up with a burst of frames.
+///
+/// ```
+/// let mut limiter = cpf::util::FrameLimiter::new(60.0);
+/// let start = std::time::Instant::now();
+/// for _ in 0..3 {
+///     // draw a frame
+///     limiter.wait();
+/// }
+/// assert!(start.elapsed() >= limiter.interval() * 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FrameLimiter {
+    interval: Duration,
+    deadline: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// Limiter for at most `fps` frames per second.
+    ///
+    /// # Panics
+    ///
+    /// If `fps` is not a positive, finite number.
+    pub fn new(fps: f64) -> Self {
+        assert!(
+            fps.is_finite() && fps > 0.0,
+            "can't limit to {fps} frames per second"
+        );
+        Self::from_interval(Duration::from_secs_f64(1.0 / fps))
+    }
+
+    /// Limiter for frames at least `interval` apart.
+    pub fn from_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            deadline: None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Time at which the next frame is due, to be called once per frame after drawing it.
+    pub fn next_frame(&mut self) -> Instant {
+        let now = Instant::now();
+        let deadline = match self.deadline {
+            // never in the past, a late frame doesn't earn the following ones extra time
+            Some(deadline) => (deadline + self.interval).max(now),
+            None => now + self.interval,
+        };
+        self.deadline = Some(deadline);
+        deadline
+    }
+
+    /// Sleep until the next frame is due, see [`FrameLimiter::next_frame`].
+    pub fn wait(&mut self) {
+        let deadline = self.next_frame();
+        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    }
+}